@@ -6,7 +6,12 @@ pub enum Error {
     IoError(std::io::Error),
     InconsistentByteLength(usize, usize),
     IncompatibleOptions(String, String),
+    InvalidOption(String, String),
     MissingBuilderField(String),
+    IncompatibleSampleFormat(hound::SampleFormat, u16, String),
+    Unsupported(String),
+    MismatchedStems(String),
+    TaskFailed(String),
 }
 
 impl fmt::Display for Error {
@@ -22,7 +27,17 @@ impl fmt::Display for Error {
             Self::IncompatibleOptions(first, second) => {
                 write!(f, "Incompatible options encountered: {first}, {second}.")
             }
+            Self::InvalidOption(option, reason) => {
+                write!(f, "Invalid option: {option} {reason}.")
+            }
             Self::MissingBuilderField(str) => write!(f, "Missing builder field: {str}"),
+            Self::IncompatibleSampleFormat(format, bits, sample_type) => write!(
+                f,
+                "Incompatible sample format - cannot read {bits}-bit {format:?} samples as {sample_type}"
+            ),
+            Self::Unsupported(str) => write!(f, "Unsupported: {str}"),
+            Self::MismatchedStems(str) => write!(f, "Mismatched stems: {str}"),
+            Self::TaskFailed(str) => write!(f, "Splitting task failed: {str}"),
         }
     }
 }
@@ -34,6 +49,10 @@ impl Error {
         Self::IncompatibleOptions(first.to_string(), second.to_string())
     }
 
+    pub fn invalid_option(option: &str, reason: &str) -> Self {
+        Self::InvalidOption(option.to_string(), reason.to_string())
+    }
+
     pub fn inconsistent_byte_length(first: usize, second: usize) -> Self {
         Self::InconsistentByteLength(first, second)
     }
@@ -41,6 +60,26 @@ impl Error {
     pub fn missing_builder_field(field: &str) -> Self {
         Self::MissingBuilderField(field.to_string())
     }
+
+    pub fn unsupported(what: &str) -> Self {
+        Self::Unsupported(what.to_string())
+    }
+
+    pub fn mismatched_stems(reason: &str) -> Self {
+        Self::MismatchedStems(reason.to_string())
+    }
+
+    pub fn task_failed(reason: &str) -> Self {
+        Self::TaskFailed(reason.to_string())
+    }
+
+    pub fn incompatible_sample_format(
+        format: hound::SampleFormat,
+        bits: u16,
+        sample_type: &str,
+    ) -> Self {
+        Self::IncompatibleSampleFormat(format, bits, sample_type.to_string())
+    }
 }
 
 impl From<hound::Error> for Error {
@@ -1,15 +1,21 @@
-use std::{io::Cursor, path::Path, time::Duration};
+use std::{io::Cursor, path::Path, sync::Arc};
 
 use symphonia::core::{
     codecs::CodecParameters,
-    conv::IntoSample,
-    formats::{FormatOptions, FormatReader},
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
     io::{MediaSourceStream, MediaSourceStreamOptions},
 };
 
 use symphonia::default::formats::AiffReader;
 
-use crate::{AudioChunk, error::Error, opts::SplitOpts, result::SplitResult};
+use crate::{
+    BytesPerMillisecond,
+    error::Error,
+    formats::common::{align_to_frame, levels, part_boundary, shared_buffer},
+    opts::{SplitMode, SplitOpts},
+    plan::{PlannedChunk, SplitPlan},
+    result::{CutReason, SplitResult},
+};
 
 pub struct AiffSplitter {
     reader: AiffReader,
@@ -41,36 +47,196 @@ impl AiffSplitter {
 
         Ok(Self { reader })
     }
+
+    /// The number of bytes making up a single sample of audio.
+    fn bytes_per_sample(&self) -> usize {
+        self.codec().bits_per_sample.unwrap_or(16).div_ceil(8) as usize
+    }
+
+    /// The number of bytes making up a single frame of audio.
+    fn frame_width(&self) -> usize {
+        let channels = self.codec().channels.map(|x| x.count()).unwrap_or(1);
+
+        channels * self.bytes_per_sample()
+    }
+
+    fn rewind(&mut self) -> Result<(), Error> {
+        let track_id = self.reader.default_track().unwrap().id;
+        self.reader
+            .seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 0, track_id })?;
+
+        Ok(())
+    }
+
+    /// The number of bytes of audio, counted by reading through it. The frame count in the header
+    /// can't be relied on, as it's worked out from a chunk size that includes more than the audio.
+    fn data_len(&mut self) -> Result<usize, Error> {
+        self.rewind()?;
+
+        let mut len = 0;
+        while let Ok(packet) = self.reader.next_packet() {
+            len += packet.buf().len();
+        }
+
+        Ok(len)
+    }
 }
 
 impl crate::AudioSplitter for AiffSplitter {
     type ByteSize = u8;
     type CodecParams = CodecParameters;
 
+    /// Works out every cut first, then reads the audio into a single buffer that the chunks are
+    /// views into.
     fn split_audio(
         &mut self,
         opts: SplitOpts,
     ) -> Result<SplitResult<Self::ByteSize, Self::CodecParams>, Error> {
-        let byte_limit = opts.frame_size();
+        let plan = self.plan(opts)?;
 
-        let mut bigvec: Vec<AudioChunk<Self::ByteSize>> = Vec::new();
+        self.apply(plan)
+    }
 
-        let mut current_vec: Vec<u8> = Vec::new();
+    /// Works out every cut from the length of the audio alone, without holding on to any of it.
+    fn plan(&mut self, opts: SplitOpts) -> Result<SplitPlan, Error> {
+        if let SplitMode::Pauses(_) = opts.mode() {
+            return Err(Error::unsupported(
+                "splitting AIFF audio on silence is not supported yet",
+            ));
+        }
 
-        while let Ok(packet) = self.reader.next_packet() {
-            let packet_buf = packet.buf();
-            if (packet_buf.len() + current_vec.len()) > byte_limit {
-                let limit = byte_limit - current_vec.len();
-                current_vec.extend(&packet_buf[0..limit]);
-                let audio_chunk = AudioChunk::new(&current_vec, 0, 0);
-                bigvec.push(audio_chunk);
-                current_vec.clear();
-                current_vec.extend(&packet_buf[0..limit]);
+        // AIFF audio isn't searched for silence yet, so the silence threshold is ignored and
+        // anything that would change how silence is found or used is turned away.
+        if let Some(option) = opts.silence_option() {
+            return Err(Error::unsupported(&format!(
+                "{option} needs silence detection, which isn't supported for AIFF audio yet"
+            )));
+        }
+
+        let frame_width = self.frame_width();
+        let total = align_to_frame(self.data_len()?, frame_width);
+
+        let bytes_per_sample = self.bytes_per_sample();
+        let to_bytes = |samples: usize| align_to_frame(samples * bytes_per_sample, frame_width);
+        let overlap = to_bytes(opts.overlap());
+        let min_len = opts
+            .min_len()
+            .map(to_bytes)
+            .unwrap_or_default()
+            .max(frame_width);
+        let max_len = opts
+            .max_len()
+            .map(to_bytes)
+            .unwrap_or(usize::MAX)
+            .max(min_len);
+
+        let sample_rate = self.codec().sample_rate();
+
+        // How long the chunk at `index` should be, given that it starts at `offset`.
+        let chunk_len = |index: usize, offset: usize| {
+            let len = match opts.mode() {
+                SplitMode::Size(size) => to_bytes(*size),
+                SplitMode::Parts(parts) if index + 1 < *parts => {
+                    part_boundary(0, total, *parts, index, frame_width).saturating_sub(offset)
+                }
+                SplitMode::Timestamps(cuts) => {
+                    return cuts
+                        .iter()
+                        .map(|cut| to_bytes(*cut))
+                        .find(|cut| *cut > offset)
+                        .map_or(usize::MAX, |cut| cut - offset);
+                }
+                SplitMode::Parts(_) | SplitMode::Pauses(_) => return usize::MAX,
+            };
+
+            len.clamp(min_len, max_len)
+        };
+
+        let mut planned = Vec::new();
+        let mut offset = 0;
+        // Where the next chunk starts, which is before `offset` when chunks overlap.
+        let mut start = 0;
+        while offset < total {
+            let len = chunk_len(planned.len(), offset);
+            let (end, reason) = if total - offset > len {
+                (offset + len, CutReason::Target)
             } else {
-                current_vec.extend(packet.buf());
-            }
+                (total, CutReason::EndOfInput)
+            };
+
+            planned.push(
+                PlannedChunk::new(sample_rate, start / frame_width, end / frame_width)
+                    .with_core_range(offset / frame_width, end / frame_width)
+                    .with_cut_reason(reason),
+            );
+
+            start = end - overlap.min(end - start);
+            offset = end;
+        }
+
+        Ok(SplitPlan::new(planned))
+    }
+
+    fn apply(
+        &mut self,
+        plan: SplitPlan,
+    ) -> Result<SplitResult<Self::ByteSize, Self::CodecParams>, Error> {
+        let frame_width = self.frame_width();
+        let bytes_per_sample = self.bytes_per_sample();
+
+        // Every chunk is a view into the same buffer, which only reaches as far as the plan does.
+        let end = plan.iter().map(|x| x.end_frame()).max().unwrap_or_default();
+        let mut bytes = shared_buffer::<u8>(end * frame_width);
+        let buffer = Arc::get_mut(&mut bytes).expect("a new buffer isn't shared yet");
+
+        self.rewind()?;
+        let mut filled = 0;
+        while filled < buffer.len()
+            && let Ok(packet) = self.reader.next_packet()
+        {
+            let len = packet.buf().len().min(buffer.len() - filled);
+            buffer[filled..filled + len].copy_from_slice(&packet.buf()[..len]);
+            filled += len;
+        }
+        if filled < buffer.len() {
+            return Err(Error::invalid_option(
+                "plan",
+                "has a chunk that runs past the end of the audio",
+            ));
         }
 
-        Ok(SplitResult::new(bigvec, self.codec()))
+        let threshold = plan.silence_threshold();
+        let mut bigvec = Vec::with_capacity(plan.len());
+        for (index, planned) in plan.into_iter().enumerate() {
+            let range = planned.start_frame() * frame_width..planned.end_frame() * frame_width;
+            let (peak, rms) = pcm_levels(&bytes[range.clone()], bytes_per_sample);
+            bigvec.push(
+                planned
+                    .to_chunk(bytes.clone(), range, frame_width, index)
+                    .with_levels(peak, rms),
+            );
+        }
+
+        Ok(SplitResult::new(bigvec, self.codec()).with_silence_threshold(threshold))
     }
 }
+
+/// The peak and RMS levels, relative to full scale, of big-endian signed PCM samples that are
+/// each `bytes_per_sample` bytes wide.
+fn pcm_levels(bytes: &[u8], bytes_per_sample: usize) -> (f32, f32) {
+    let full_scale = (1u64 << (bytes_per_sample * 8 - 1)) as f32;
+    let samples: Vec<f32> = bytes
+        .chunks_exact(bytes_per_sample)
+        .map(|sample| {
+            // Shift the sample into the top of an i32 so its sign bit lines up.
+            let value = sample
+                .iter()
+                .fold(0u32, |acc, byte| (acc << 8) | *byte as u32);
+            let value =
+                (value << (32 - bytes_per_sample * 8)) as i32 >> (32 - bytes_per_sample * 8);
+            value as f32
+        })
+        .collect();
+
+    levels(&samples, full_scale)
+}
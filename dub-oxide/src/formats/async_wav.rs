@@ -0,0 +1,271 @@
+//! Splitting WAV audio that arrives through an [`AsyncRead`], such as a request body.
+//!
+//! The cut logic is the same as [`WavSplitter`](crate::WavSplitter)'s. It runs on tokio's
+//! blocking thread pool and is fed from the async reader through a small bounded channel, so the
+//! runtime is never blocked and only a few blocks of the upload are held in memory at once.
+
+use std::{
+    future::Future,
+    io::{self, Read, Seek, SeekFrom},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use hound::{WavReader, WavSpec};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    sync::{mpsc, oneshot},
+    task::{JoinError, JoinHandle},
+};
+use tokio_stream::Stream;
+
+use crate::{
+    AudioChunk,
+    error::Error,
+    formats::wav::{WavChunks, WavSample, check_sample_type},
+    opts::SplitOpts,
+};
+
+/// How many bytes are read from the async reader at a time.
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// How many blocks of input, and how many finished chunks, can be waiting at once.
+const QUEUE_LEN: usize = 4;
+
+/// Splits WAV audio read from an [`AsyncRead`] into a [`Stream`] of chunks.
+///
+/// Samples are decoded as `T`, which defaults to `i16`, and must be able to hold the file's
+/// samples as with [`WavSplitter::with_sample_type`](crate::WavSplitter::with_sample_type).
+///
+/// The input is read as it's needed and never rewound, so
+/// [`auto_silence_threshold`](crate::opts::SplitOptsBuilder::auto_silence_threshold) isn't
+/// supported. Must be used from within a tokio runtime.
+pub struct AsyncWavSplitter<T = i16> {
+    spec: WavSpec,
+    opts: oneshot::Sender<SplitOpts>,
+    chunks: mpsc::Receiver<Result<AudioChunk<T>, Error>>,
+    task: JoinHandle<()>,
+}
+
+impl<T> AsyncWavSplitter<T>
+where
+    T: WavSample + 'static,
+{
+    /// Starts reading `reader`, returning once the WAV header has been read.
+    pub async fn open<R>(reader: R) -> Result<Self, Error>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let (blocks_tx, blocks_rx) = mpsc::channel(QUEUE_LEN);
+        let (spec_tx, spec_rx) = oneshot::channel();
+        let (opts_tx, opts_rx) = oneshot::channel();
+        let (chunks_tx, chunks_rx) = mpsc::channel(QUEUE_LEN);
+
+        tokio::spawn(pump(reader, blocks_tx));
+        let task = tokio::task::spawn_blocking(move || {
+            split::<T>(ChannelReader::new(blocks_rx), spec_tx, opts_rx, chunks_tx)
+        });
+
+        let Ok(spec) = spec_rx.await else {
+            return Err(match task.await {
+                Err(err) => task_failed(err),
+                Ok(()) => Error::task_failed("stopped before reading the header"),
+            });
+        };
+
+        Ok(Self {
+            spec: spec?,
+            opts: opts_tx,
+            chunks: chunks_rx,
+            task,
+        })
+    }
+
+    pub fn codec(&self) -> WavSpec {
+        self.spec
+    }
+
+    /// Splits the rest of the input into chunks, which are produced as soon as each cut is known.
+    ///
+    /// If splitting fails partway through, the error is the last item in the stream.
+    pub fn chunks(self, opts: SplitOpts) -> impl Stream<Item = Result<AudioChunk<T>, Error>> {
+        // If the splitting task has already stopped, the stream reports why once it's drained.
+        let _ = self.opts.send(opts);
+
+        ChunkStream {
+            chunks: self.chunks,
+            task: Some(self.task),
+        }
+    }
+}
+
+fn task_failed(err: JoinError) -> Error {
+    Error::task_failed(&err.to_string())
+}
+
+/// The chunks handed out by the splitting task, followed by an error if the task panicked, so a
+/// stream that was cut short can be told apart from one that reached the end of the input.
+struct ChunkStream<T> {
+    chunks: mpsc::Receiver<Result<AudioChunk<T>, Error>>,
+    /// The splitting task, until it's been checked on.
+    task: Option<JoinHandle<()>>,
+}
+
+impl<T> Stream for ChunkStream<T> {
+    type Item = Result<AudioChunk<T>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(chunk) = ready!(self.chunks.poll_recv(cx)) {
+            return Poll::Ready(Some(chunk));
+        }
+
+        // Every chunk has been handed out, so all that's left is how the task finished.
+        let Some(task) = self.task.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let finished = ready!(Pin::new(task).poll(cx));
+        self.task = None;
+
+        Poll::Ready(finished.err().map(|err| Err(task_failed(err))))
+    }
+}
+
+/// Reads `reader` a block at a time, handing each block to the splitting task. Stops once the
+/// input runs out or the splitting task hangs up.
+async fn pump<R>(mut reader: R, blocks: mpsc::Sender<io::Result<Vec<u8>>>)
+where
+    R: AsyncRead + Unpin,
+{
+    loop {
+        let mut block = vec![0; BLOCK_SIZE];
+        let block = match reader.read(&mut block).await {
+            Ok(0) => return,
+            Ok(len) => {
+                block.truncate(len);
+                Ok(block)
+            }
+            Err(err) => Err(err),
+        };
+
+        let failed = block.is_err();
+        if blocks.send(block).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Runs on the blocking thread pool, reading the header, waiting for the split options and then
+/// handing out chunks as they're cut. Stops early if the stream of chunks is dropped.
+fn split<T>(
+    reader: ChannelReader,
+    spec: oneshot::Sender<Result<WavSpec, Error>>,
+    opts: oneshot::Receiver<SplitOpts>,
+    chunks: mpsc::Sender<Result<AudioChunk<T>, Error>>,
+) where
+    T: WavSample,
+{
+    let reader = WavReader::new(reader)
+        .map_err(Error::from)
+        .and_then(|reader| check_sample_type::<T>(&reader.spec()).map(|_| reader));
+    let mut reader = match reader {
+        Ok(reader) => {
+            let _ = spec.send(Ok(reader.spec()));
+            reader
+        }
+        Err(err) => {
+            let _ = spec.send(Err(err));
+            return;
+        }
+    };
+
+    let Ok(opts) = opts.blocking_recv() else {
+        return;
+    };
+
+    if opts.noise_floor_margin().is_some() {
+        let _ = chunks.blocking_send(Err(Error::unsupported(
+            "picking the silence threshold automatically needs the whole input up front",
+        )));
+        return;
+    }
+
+    for chunk in WavChunks::<_, T>::new(&mut reader, opts) {
+        if chunks.blocking_send(chunk).is_err() {
+            return;
+        }
+    }
+}
+
+/// A blocking reader over the blocks sent by [`pump`].
+struct ChannelReader {
+    blocks: mpsc::Receiver<io::Result<Vec<u8>>>,
+    block: Vec<u8>,
+    pos: usize,
+}
+
+impl ChannelReader {
+    fn new(blocks: mpsc::Receiver<io::Result<Vec<u8>>>) -> Self {
+        Self {
+            blocks,
+            block: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.block.len() {
+            match self.blocks.blocking_recv() {
+                Some(block) => {
+                    self.block = block?;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.block.len() - self.pos);
+        buf[..len].copy_from_slice(&self.block[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+/// The input is only ever read forwards, but the chunk iterator needs a seekable reader to rewind
+/// when picking the silence threshold automatically, which is turned away before it gets here.
+impl Seek for ChannelReader {
+    fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "an async stream can't be rewound",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn a_panicking_task_ends_the_stream_with_an_error() {
+        let (chunks_tx, chunks_rx) = mpsc::channel(QUEUE_LEN);
+        let task = tokio::task::spawn_blocking(move || {
+            let _ = chunks_tx.blocking_send(Ok(AudioChunk::new(&[0i16; 4], 1, 8000, 0)));
+            panic!("decoding went wrong");
+        });
+
+        let stream = ChunkStream {
+            chunks: chunks_rx,
+            task: Some(task),
+        };
+        let res: Vec<_> = stream.collect().await;
+
+        assert_eq!(res.len(), 2);
+        assert!(res[0].is_ok());
+        assert!(matches!(res[1], Err(Error::TaskFailed(_))));
+    }
+}
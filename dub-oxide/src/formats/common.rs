@@ -1,7 +1,16 @@
-use hound::Sample;
+use std::{cmp::Reverse, iter, ops::Range, sync::Arc, time::Duration};
+
 use num::NumCast;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-use crate::AudioSplitter;
+use super::kernel::Kernel;
+pub use super::kernel::levels;
+use super::kernel::{channel_rms, mixdown_rms};
+use crate::{
+    opts::{ChannelAnalysis, CutPolicy},
+    vad::VoiceActivityDetector,
+};
 
 pub fn dbfs(rms: f32) -> f32 {
     if rms == 0.0 {
@@ -11,43 +20,470 @@ pub fn dbfs(rms: f32) -> f32 {
     }
 }
 
-pub fn rms<T>(samples: Vec<T>) -> f32
+/// The RMS level of `samples`, relative to a sample value of `full_scale`.
+pub fn rms<T>(samples: &[T], full_scale: f32) -> f32
 where
-    T: num::NumCast + Clone + hound::Sample,
+    T: Kernel,
 {
-    let sum_sq: f32 = samples
-        .iter()
-        .cloned()
-        .map(|s| {
-            let sample: f32 = NumCast::from(s).unwrap();
-            (sample / i16::MAX as f32).powi(2)
+    levels(samples, full_scale).1
+}
+
+/// Rounds `pos` down to the nearest frame boundary.
+pub fn align_to_frame(pos: usize, frame_width: usize) -> usize {
+    pos - pos % frame_width.max(1)
+}
+
+/// Averages interleaved samples with `frame_width` channels down to a single channel, relative to
+/// a sample value of `full_scale`.
+pub fn mixdown<T>(samples: &[T], frame_width: usize, full_scale: f32) -> Vec<f32>
+where
+    T: NumCast + Copy,
+{
+    let frame_width = frame_width.max(1);
+
+    samples
+        .chunks(frame_width)
+        .map(|frame| {
+            let sum: f32 = frame
+                .iter()
+                .map(|x| <f32 as NumCast>::from(*x).unwrap_or_default())
+                .sum();
+            sum / frame.len() as f32 / full_scale
         })
-        .sum();
-    (sum_sq / samples.len() as f32).sqrt()
+        .collect()
 }
 
-pub fn find_silent_position<T, S>(
-    bytes: &[T],
-    frame_size: usize,
-    volume_threshold: f32,
-) -> Option<usize>
+/// Picks out the samples of a single channel from interleaved samples.
+fn channel<T>(samples: &[T], index: usize, frame_width: usize) -> Vec<f32>
 where
-    T: PartialEq + Copy + num::Num + Default + Sample + NumCast,
-    S: AudioSplitter,
+    T: NumCast + Copy,
 {
-    for (i, chunk) in bytes.chunks(frame_size).enumerate().rev() {
-        let rms_value = rms::<T>(chunk.to_owned());
-        let db = dbfs(rms_value);
+    samples
+        .iter()
+        .skip(index)
+        .step_by(frame_width.max(1))
+        .map(|x| <f32 as NumCast>::from(*x).unwrap_or_default())
+        .collect()
+}
+
+/// What was measured in a single analysis window.
+enum Measured {
+    /// The window's level, in dBFS.
+    Level(f32),
+    /// Whether the voice activity detector found speech in the window.
+    Speech(bool),
+}
+
+/// Finds silent regions in a run of samples.
+///
+/// Samples are checked a window at a time, with each window starting `hop` samples after the one
+/// before it (so windows overlap if `hop` is shorter than `window`). A region becomes silent once a window drops to
+/// `enter_threshold` dBFS, and stays silent until a window rises above `exit_threshold`, so a
+/// level hovering around a single threshold doesn't flicker in and out of silence.
+#[derive(Clone, Debug)]
+pub struct SilenceDetector {
+    /// The length of each window, in samples.
+    pub window: usize,
+    /// The distance between the start of one window and the next, in samples.
+    pub hop: usize,
+    /// The number of samples in each frame. Every window (and so every cut) starts on a frame.
+    pub frame_width: usize,
+    /// The value of a full-scale sample.
+    pub full_scale: f32,
+    pub enter_threshold: f32,
+    pub exit_threshold: f32,
+    /// The shortest region, in samples, that counts as silence.
+    pub min_len: usize,
+    /// If set, a window is silent whenever this finds no speech in it, whatever its level.
+    pub vad: Option<VoiceActivityDetector>,
+    /// How the channels of each frame are combined.
+    pub channels: ChannelAnalysis,
+}
+
+impl SilenceDetector {
+    fn windows<'a, T>(&self, bytes: &'a [T]) -> impl Iterator<Item = (usize, &'a [T])> {
+        let frame_width = self.frame_width.max(1);
+        let window = align_to_frame(self.window, frame_width).max(frame_width);
+        let hop = align_to_frame(self.hop, frame_width).clamp(frame_width, window);
+
+        (0..bytes.len())
+            .step_by(hop)
+            // Stop once a window has reached the end, rather than trailing off into ever shorter
+            // windows.
+            .take_while(move |pos| *pos == 0 || pos - hop + window < bytes.len())
+            .map(move |pos| (pos, &bytes[pos..(pos + window).min(bytes.len())]))
+    }
+
+    /// Applies `measure` to every window of `bytes`, in order. With the `parallel` feature the
+    /// windows are measured across threads.
+    fn map_windows<T, U>(
+        &self,
+        bytes: &[T],
+        measure: impl Fn(&[T]) -> U + Sync,
+    ) -> Vec<(Range<usize>, U)>
+    where
+        T: Sync,
+        U: Send,
+    {
+        let windows: Vec<Range<usize>> = self
+            .windows(bytes)
+            .map(|(pos, chunk)| pos..pos + chunk.len())
+            .collect();
+
+        #[cfg(feature = "parallel")]
+        let windows = windows.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let windows = windows.into_iter();
+
+        windows
+            .map(|range| {
+                let measured = measure(&bytes[range.clone()]);
+                (range, measured)
+            })
+            .collect()
+    }
 
-        if db <= volume_threshold {
-            return Some(i * frame_size);
+    /// The RMS level of `samples` relative to full scale, after combining channels. A window is
+    /// silent whenever this is at or below the threshold.
+    ///
+    /// Channels are measured in place, so this doesn't allocate.
+    pub fn level<T>(&self, samples: &[T]) -> f32
+    where
+        T: NumCast + Copy + Kernel,
+    {
+        let frame_width = self.frame_width.max(1);
+        if frame_width == 1 {
+            return rms(samples, self.full_scale);
         }
+
+        let channel_rms = |index: usize| channel_rms(samples, index, frame_width, self.full_scale);
+
+        match &self.channels {
+            ChannelAnalysis::Mixdown => mixdown_rms(samples, frame_width, self.full_scale),
+            // Every channel is silent exactly when the loudest one is.
+            ChannelAnalysis::AllSilent => (0..frame_width).map(channel_rms).fold(0.0, f32::max),
+            ChannelAnalysis::AnySilent => (0..frame_width)
+                .map(channel_rms)
+                .fold(f32::INFINITY, f32::min),
+            ChannelAnalysis::Channels(selected) => selected
+                .iter()
+                .filter(|index| **index < frame_width)
+                .map(|index| channel_rms(*index))
+                .fold(0.0, f32::max),
+        }
+    }
+
+    /// Whether `vad` finds speech in `samples`, after combining channels.
+    fn has_speech<T>(&self, vad: &VoiceActivityDetector, samples: &[T]) -> bool
+    where
+        T: NumCast + Copy,
+    {
+        let frame_width = self.frame_width.max(1);
+        let is_speech =
+            |index: usize| vad.is_speech(&channel(samples, index, frame_width), 1, self.full_scale);
+
+        match &self.channels {
+            ChannelAnalysis::Mixdown => vad.is_speech(samples, frame_width, self.full_scale),
+            ChannelAnalysis::AllSilent => (0..frame_width).any(is_speech),
+            ChannelAnalysis::AnySilent => (0..frame_width).all(is_speech),
+            ChannelAnalysis::Channels(selected) => selected
+                .iter()
+                .filter(|index| **index < frame_width)
+                .any(|index| is_speech(*index)),
+        }
+    }
+
+    /// Whether `samples` count as silence at `threshold` dBFS, or contain no speech if there's a
+    /// voice activity detector.
+    pub fn is_silent<T>(&self, samples: &[T], threshold: f32) -> bool
+    where
+        T: NumCast + Copy + Kernel,
+    {
+        match &self.vad {
+            Some(vad) => !self.has_speech(vad, samples),
+            None => dbfs(self.level(samples)) <= threshold,
+        }
+    }
+
+    /// The silent regions in `bytes`, in order.
+    pub fn regions<T>(&self, bytes: &[T]) -> Vec<Range<usize>>
+    where
+        T: NumCast + Copy + Kernel + Sync,
+    {
+        // The level of every window is measured up front, so only picking which windows are
+        // silent depends on the windows before.
+        let windows = self.map_windows(bytes, |chunk| match &self.vad {
+            Some(vad) => Measured::Speech(self.has_speech(vad, chunk)),
+            None => Measured::Level(dbfs(self.level(chunk))),
+        });
+
+        let mut regions = Vec::new();
+        let mut start = None;
+        // The end of the last silent window, which is where the current region ends.
+        let mut end = 0;
+        for (window, measured) in windows {
+            let threshold = if start.is_some() {
+                self.exit_threshold
+            } else {
+                self.enter_threshold
+            };
+
+            let silent = match measured {
+                Measured::Level(level) => level <= threshold,
+                Measured::Speech(speech) => !speech,
+            };
+            if silent {
+                start.get_or_insert(window.start);
+                end = window.end;
+            } else if let Some(start) = start.take() {
+                regions.push(start..end);
+            }
+        }
+
+        if let Some(start) = start {
+            regions.push(start..end);
+        }
+
+        regions.retain(|region| region.len() >= self.min_len);
+        regions
     }
 
-    Some(bytes.len())
+    /// The part of `bytes` from the start of the first window that isn't silent to the end of the
+    /// last one, or `None` if it's silent throughout.
+    pub fn audible_range<T>(&self, bytes: &[T]) -> Option<Range<usize>>
+    where
+        T: NumCast + Copy + Kernel + Sync,
+    {
+        let mut audible = self
+            .map_windows(bytes, |chunk| self.is_silent(chunk, self.enter_threshold))
+            .into_iter()
+            .filter(|(_, silent)| !silent)
+            .map(|(window, _)| window);
+        let first = audible.next()?;
+        let end = audible.next_back().map_or(first.end, |last| last.end);
+
+        Some(first.start..end)
+    }
+
+    /// The level, in dBFS, of a window centred on `pos`.
+    pub fn level_around<T>(&self, bytes: &[T], pos: usize) -> f32
+    where
+        T: NumCast + Copy + Kernel,
+    {
+        let half = align_to_frame(self.window / 2, self.frame_width.max(1));
+        let end = (pos + half).min(bytes.len());
+        let start = pos.saturating_sub(half).min(end);
+
+        dbfs(self.level(&bytes[start..end]))
+    }
+
+    fn middle(&self, region: &Range<usize>) -> usize {
+        region.start + align_to_frame(region.len() / 2, self.frame_width)
+    }
+
+    /// The middle of the window with the lowest RMS level, whether or not it's silent. Ties go to
+    /// the window closest to `target`.
+    fn quietest<T>(&self, bytes: &[T], target: usize) -> Option<usize>
+    where
+        T: NumCast + Copy + Kernel + Sync,
+    {
+        self.map_windows(bytes, |chunk| self.level(chunk))
+            .into_iter()
+            .map(|(window, level)| (self.middle(&window), level))
+            .min_by(|(a_pos, a_rms), (b_pos, b_rms)| {
+                a_rms
+                    .total_cmp(b_rms)
+                    .then(a_pos.abs_diff(target).cmp(&b_pos.abs_diff(target)))
+            })
+            .map(|(pos, _)| pos)
+    }
+
+    /// Picks where in `range` to cut `bytes` using `policy`, aiming for `target`. Apart from
+    /// [`CutPolicy::QuietestWindow`], the cut lands in the middle of the part of a silent region
+    /// that falls in `range`.
+    ///
+    /// `bytes` starts where the chunk being cut starts. If that's where an earlier chunk was cut
+    /// (`after_cut`), silence right at the start is the rest of the pause it was cut in, so it's
+    /// passed over for any other pause in `range`: cutting in its middle again would only make each
+    /// chunk shorter than the last. If it's the only pause in reach, the cut goes where it ends
+    /// instead of in the middle of sound.
+    ///
+    /// Returns `None` if the policy found nowhere suitable.
+    pub fn find_cut<T>(
+        &self,
+        bytes: &[T],
+        range: Range<usize>,
+        target: usize,
+        policy: CutPolicy,
+        after_cut: bool,
+    ) -> Option<usize>
+    where
+        T: NumCast + Copy + Kernel + Sync,
+    {
+        if policy == CutPolicy::QuietestWindow {
+            return self
+                .quietest(&bytes[range.clone()], target.saturating_sub(range.start))
+                .map(|pos| pos + range.start);
+        }
+
+        // Regions are found from the start of the chunk rather than the start of the range, so a
+        // pause the range starts partway into is still known to be the one the chunk starts with.
+        let mut regions: Vec<Range<usize>> = Vec::new();
+        let mut leading = None;
+        for region in self.regions(&bytes[..range.end]) {
+            let starts_chunk = after_cut && region.start == 0;
+            let region = region.start.max(range.start)..region.end;
+            if region.is_empty() {
+                continue;
+            }
+
+            if starts_chunk {
+                leading = Some(region);
+            } else {
+                regions.push(region);
+            }
+        }
+
+        if regions.is_empty() {
+            return leading.map(|region| region.end);
+        }
+
+        let region = match policy {
+            CutPolicy::LastSilence => regions.last(),
+            CutPolicy::ClosestToTarget => regions
+                .iter()
+                .min_by_key(|region| self.middle(region).abs_diff(target)),
+            _ => regions
+                .iter()
+                .min_by_key(|region| (Reverse(region.len()), self.middle(region).abs_diff(target))),
+        };
+
+        region.map(|region| self.middle(region))
+    }
 }
 
-pub fn bytes_to_timestamp(offset: usize, bytes_per_ms: usize) -> usize {
-    let timestamp = ((offset / bytes_per_ms) as f64).floor();
-    timestamp as usize
+/// Estimates a silence threshold (in dBFS) from the level of every analysis window in a file.
+///
+/// The 10th percentile of `levels` is taken as the noise floor, and the 90th as the level of the
+/// louder parts. The threshold is `margin` dB above the noise floor, but never more than halfway
+/// between the two, so files with hardly any quiet parts still have something to cut on.
+pub fn estimate_threshold(mut levels: Vec<f32>, margin: f32) -> Option<f32> {
+    if levels.is_empty() {
+        return None;
+    }
+
+    levels.sort_unstable_by(f32::total_cmp);
+    let percentile = |p: f32| levels[((levels.len() - 1) as f32 * p).round() as usize];
+    let noise_floor = percentile(0.1);
+    let loud = percentile(0.9);
+
+    Some(
+        (noise_floor + margin)
+            .min((noise_floor + loud) / 2.0)
+            .max(noise_floor),
+    )
+}
+
+/// The position where part `index` (counting from zero) of `parts` equal parts ends, when
+/// splitting the samples between `start` and `total`.
+pub fn part_boundary(
+    start: usize,
+    total: usize,
+    parts: usize,
+    index: usize,
+    frame_width: usize,
+) -> usize {
+    let len = (total - start) as u128;
+    let end = (len * (index as u128 + 1) / parts as u128) as usize;
+
+    start + align_to_frame(end, frame_width)
+}
+
+/// A buffer of `len` default values for chunks to share, to be filled in place through
+/// [`Arc::get_mut`].
+///
+/// The buffer is allocated once at its full size. Collecting samples straight into an `Arc<[T]>`
+/// gathers them into a `Vec` first and then copies them over, briefly holding the audio twice.
+pub fn shared_buffer<T>(len: usize) -> Arc<[T]>
+where
+    T: Clone + Default,
+{
+    // `repeat_n` knows its exact length, so this allocates the `Arc` directly.
+    iter::repeat_n(T::default(), len).collect()
+}
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// The time at which frame `frames` starts, for audio sampled at `sample_rate`.
+///
+/// The result is exact to the nanosecond, so it doesn't drift however long the file is.
+pub fn frames_to_duration(frames: usize, sample_rate: u32) -> Duration {
+    let sample_rate = sample_rate.max(1) as u128;
+    let nanos = frames as u128 * NANOS_PER_SEC / sample_rate;
+
+    Duration::new(
+        (nanos / NANOS_PER_SEC) as u64,
+        (nanos % NANOS_PER_SEC) as u32,
+    )
+}
+
+/// The number of whole frames in `duration`, rounded to the nearest frame, for audio sampled at
+/// `sample_rate`.
+///
+/// Rounding (rather than truncating) means a duration returned by [`frames_to_duration`] always
+/// converts back to the frame it came from.
+pub fn duration_to_frames(duration: Duration, sample_rate: u32) -> usize {
+    let scaled = duration.as_nanos() * sample_rate as u128;
+
+    ((scaled + NANOS_PER_SEC / 2) / NANOS_PER_SEC) as usize
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use super::*;
+
+    /// Two channels of a tone that drops out for a while every 3000 frames, with the second
+    /// channel quieter and out of step with the first.
+    fn samples() -> Vec<i16> {
+        (0..40_000)
+            .flat_map(|frame: i32| {
+                let level = if frame % 3000 < 2000 { 8000 } else { 0 };
+                let wave = (frame % 40 - 20) * level / 20;
+
+                [wave as i16, (-wave / 3) as i16]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parallel_windows_match_measuring_them_in_order() {
+        let samples = samples();
+
+        for (window, hop, channels) in [
+            (800, 800, ChannelAnalysis::Mixdown),
+            (800, 200, ChannelAnalysis::AllSilent),
+            (333, 101, ChannelAnalysis::AnySilent),
+            (1000, 250, ChannelAnalysis::Channels(vec![1])),
+        ] {
+            let detector = SilenceDetector {
+                window,
+                hop,
+                frame_width: 2,
+                full_scale: i16::MAX as f32 + 1.0,
+                enter_threshold: -30.0,
+                exit_threshold: -30.0,
+                min_len: 0,
+                vad: None,
+                channels,
+            };
+
+            let in_order: Vec<(Range<usize>, f32)> = detector
+                .windows(&samples)
+                .map(|(pos, chunk)| (pos..pos + chunk.len(), detector.level(chunk)))
+                .collect();
+            let parallel = detector.map_windows(&samples, |chunk| detector.level(chunk));
+
+            assert_eq!(parallel, in_order);
+        }
+    }
 }
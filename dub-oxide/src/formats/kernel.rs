@@ -0,0 +1,373 @@
+//! Allocation-free level measurement.
+//!
+//! Working out the RMS level of every analysis window is the hot loop when splitting on silence,
+//! so each sample type gets a kernel that measures a slice in a single pass. On x86_64 the `i16`,
+//! `i32` and `f32` kernels use SSE2 (which every x86_64 CPU has) to work on four samples at a
+//! time. Everything else uses a portable scalar loop.
+//!
+//! Multichannel audio is measured straight from the interleaved samples, either a channel at a
+//! time by striding over them or a frame at a time when mixing down, so it's never copied out.
+
+/// Squares are summed in `f32` over blocks of this many samples, and the block sums are added up
+/// in `f64`, so long runs of samples don't lose precision.
+const BLOCK: usize = 1024;
+
+/// A sample type whose level can be measured without allocating.
+pub trait Kernel: Copy {
+    /// The sum of the squares of `samples` and the largest absolute value among them, both in
+    /// raw sample units.
+    fn sum_squares_and_peak(samples: &[Self]) -> (f64, f32);
+
+    /// This sample in raw sample units.
+    fn to_f32(self) -> f32;
+}
+
+impl Kernel for i8 {
+    fn sum_squares_and_peak(samples: &[Self]) -> (f64, f32) {
+        scalar(samples, |x| x as f32)
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+impl Kernel for i16 {
+    fn sum_squares_and_peak(samples: &[Self]) -> (f64, f32) {
+        #[cfg(target_arch = "x86_64")]
+        return sse2::i16s(samples);
+
+        #[cfg(not(target_arch = "x86_64"))]
+        scalar(samples, |x| x as f32)
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+impl Kernel for i32 {
+    fn sum_squares_and_peak(samples: &[Self]) -> (f64, f32) {
+        #[cfg(target_arch = "x86_64")]
+        return sse2::i32s(samples);
+
+        #[cfg(not(target_arch = "x86_64"))]
+        scalar(samples, |x| x as f32)
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+impl Kernel for f32 {
+    fn sum_squares_and_peak(samples: &[Self]) -> (f64, f32) {
+        #[cfg(target_arch = "x86_64")]
+        return sse2::f32s(samples);
+
+        #[cfg(not(target_arch = "x86_64"))]
+        scalar(samples, |x| x)
+    }
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+/// The portable kernel, one sample at a time.
+pub fn scalar<T>(samples: &[T], to_f32: impl Fn(T) -> f32) -> (f64, f32)
+where
+    T: Copy,
+{
+    samples
+        .chunks(BLOCK)
+        .fold((0.0, 0.0), |(total, peak), block| {
+            let (sum, peak) = block.iter().fold((0.0f32, peak), |(sum, peak), x| {
+                let x = to_f32(*x);
+                (sum + x * x, peak.max(x.abs()))
+            });
+
+            (total + sum as f64, peak)
+        })
+}
+
+/// The sum of the squares of `values`, summed in `f32` a block at a time like the other kernels.
+fn sum_squares(values: impl Iterator<Item = f32>) -> f64 {
+    let (total, sum, _) = values.fold((0.0, 0.0f32, 0), |(total, sum, summed), x| {
+        let sum = sum + x * x;
+        if summed + 1 == BLOCK {
+            (total + sum as f64, 0.0, 0)
+        } else {
+            (total, sum, summed + 1)
+        }
+    });
+
+    total + sum as f64
+}
+
+/// The RMS level of channel `index` of interleaved samples with `frame_width` channels, relative
+/// to a sample value of `full_scale`.
+pub fn channel_rms<T>(samples: &[T], index: usize, frame_width: usize, full_scale: f32) -> f32
+where
+    T: Kernel,
+{
+    let frame_width = frame_width.max(1);
+    let len = samples.len().saturating_sub(index).div_ceil(frame_width);
+    if len == 0 {
+        return 0.0;
+    }
+
+    let values = samples.iter().skip(index).step_by(frame_width);
+    let sum_squares = sum_squares(values.map(|x| x.to_f32()));
+
+    (sum_squares / len as f64).sqrt() as f32 / full_scale
+}
+
+/// The RMS level of interleaved samples with `frame_width` channels once they're averaged down to
+/// a single channel, relative to a sample value of `full_scale`.
+pub fn mixdown_rms<T>(samples: &[T], frame_width: usize, full_scale: f32) -> f32
+where
+    T: Kernel,
+{
+    let frames = samples.chunks(frame_width.max(1));
+    let len = frames.len();
+    if len == 0 {
+        return 0.0;
+    }
+
+    let sum_squares = sum_squares(
+        frames.map(|frame| frame.iter().map(|x| x.to_f32()).sum::<f32>() / frame.len() as f32),
+    );
+
+    (sum_squares / len as f64).sqrt() as f32 / full_scale
+}
+
+/// The peak and RMS levels of `samples`, relative to a sample value of `full_scale`.
+pub fn levels<T>(samples: &[T], full_scale: f32) -> (f32, f32)
+where
+    T: Kernel,
+{
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let (sum_squares, peak) = T::sum_squares_and_peak(samples);
+    let rms = (sum_squares / samples.len() as f64).sqrt() as f32;
+
+    (peak / full_scale, rms / full_scale)
+}
+
+/// The SSE2 kernels. SSE2 is part of the x86_64 baseline, so its intrinsics are always available
+/// and every `unsafe` block below relies on that.
+#[cfg(target_arch = "x86_64")]
+mod sse2 {
+    use std::arch::x86_64::*;
+
+    use super::{BLOCK, scalar};
+
+    /// Runs the kernel over `samples` four at a time, with `load` turning four samples into a
+    /// vector of floats. The samples left over at the end go through the scalar kernel.
+    fn run<T>(samples: &[T], load: impl Fn(&[T]) -> __m128, to_f32: impl Fn(T) -> f32) -> (f64, f32)
+    where
+        T: Copy,
+    {
+        let mut total = 0.0;
+        let mut summed = 0;
+        // SAFETY: SSE2 is always available on x86_64.
+        let (sign_bit, mut sum, mut peak) =
+            unsafe { (_mm_set1_ps(-0.0), _mm_setzero_ps(), _mm_setzero_ps()) };
+
+        let mut quads = samples.chunks_exact(4);
+        for quad in &mut quads {
+            let x = load(quad);
+            // SAFETY: SSE2 is always available on x86_64.
+            unsafe {
+                sum = _mm_add_ps(sum, _mm_mul_ps(x, x));
+                peak = _mm_max_ps(peak, _mm_andnot_ps(sign_bit, x));
+            }
+
+            summed += 4;
+            if summed == BLOCK {
+                total += horizontal(sum, |a, b| a + b) as f64;
+                // SAFETY: SSE2 is always available on x86_64.
+                sum = unsafe { _mm_setzero_ps() };
+                summed = 0;
+            }
+        }
+        total += horizontal(sum, |a, b| a + b) as f64;
+
+        let (rest, rest_peak) = scalar(quads.remainder(), to_f32);
+
+        (total + rest, horizontal(peak, f32::max).max(rest_peak))
+    }
+
+    /// Combines the four lanes of `x` with `op`.
+    fn horizontal(x: __m128, op: impl Fn(f32, f32) -> f32) -> f32 {
+        let mut lanes = [0.0; 4];
+        // SAFETY: `lanes` has room for the four floats stored.
+        unsafe { _mm_storeu_ps(lanes.as_mut_ptr(), x) };
+
+        op(op(lanes[0], lanes[1]), op(lanes[2], lanes[3]))
+    }
+
+    pub fn i16s(samples: &[i16]) -> (f64, f32) {
+        run(
+            samples,
+            // SAFETY: `quad` holds four `i16`s, which is the eight bytes loaded.
+            |quad| unsafe {
+                let x = _mm_loadl_epi64(quad.as_ptr().cast());
+                // Widen each sample to 32 bits by putting it in the top half of each lane and
+                // shifting it back down, which keeps its sign.
+                _mm_cvtepi32_ps(_mm_srai_epi32(_mm_unpacklo_epi16(x, x), 16))
+            },
+            |x| x as f32,
+        )
+    }
+
+    pub fn i32s(samples: &[i32]) -> (f64, f32) {
+        run(
+            samples,
+            // SAFETY: `quad` holds four `i32`s, which is the sixteen bytes loaded.
+            |quad| unsafe { _mm_cvtepi32_ps(_mm_loadu_si128(quad.as_ptr().cast())) },
+            |x| x as f32,
+        )
+    }
+
+    pub fn f32s(samples: &[f32]) -> (f64, f32) {
+        run(
+            samples,
+            // SAFETY: `quad` holds four `f32`s, which is the sixteen bytes loaded.
+            |quad| unsafe { _mm_loadu_ps(quad.as_ptr()) },
+            |x| x,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::NumCast;
+
+    use super::*;
+
+    /// How levels were measured before there were kernels, kept to check the kernels against.
+    fn reference_rms<T>(samples: Vec<T>, full_scale: f32) -> f32
+    where
+        T: num::NumCast + Clone + hound::Sample,
+    {
+        let sum_sq: f32 = samples
+            .iter()
+            .cloned()
+            .map(|s| {
+                let sample: f32 = NumCast::from(s).unwrap();
+                (sample / full_scale).powi(2)
+            })
+            .sum();
+        (sum_sq / samples.len() as f32).sqrt()
+    }
+
+    fn reference_peak<T>(samples: &[T], full_scale: f32) -> f32
+    where
+        T: NumCast + Copy,
+    {
+        samples
+            .iter()
+            .map(|x| <f32 as NumCast>::from(*x).unwrap_or_default().abs() / full_scale)
+            .fold(0.0, f32::max)
+    }
+
+    /// A mix of tones and pseudo-random noise between -1 and 1, with a length that isn't a
+    /// multiple of four so the scalar tail gets used too.
+    fn signal(len: usize) -> Vec<f32> {
+        let mut state = 0x9e37_79b9_u32;
+        (0..len)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let noise = state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                let tone = (i as f32 * 0.05).sin();
+                (noise * 0.3 + tone * 0.6).clamp(-1.0, 1.0)
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-4 + 1e-7,
+            "{actual} != {expected}"
+        );
+    }
+
+    fn assert_matches_reference<T>(samples: &[T], full_scale: f32)
+    where
+        T: Kernel + NumCast + hound::Sample,
+    {
+        let (peak, rms) = levels(samples, full_scale);
+        assert_close(rms, reference_rms(samples.to_vec(), full_scale));
+        assert_close(peak, reference_peak(samples, full_scale));
+    }
+
+    #[test]
+    fn kernels_match_the_reference() {
+        for len in [1, 3, 4, 7, 1000, 4099, 10_001] {
+            let signal = signal(len);
+
+            let i16s: Vec<i16> = signal.iter().map(|x| (x * 32767.0) as i16).collect();
+            let i32s: Vec<i32> = signal.iter().map(|x| (x * 2.1e9) as i32).collect();
+            let i8s: Vec<i8> = signal.iter().map(|x| (x * 127.0) as i8).collect();
+
+            assert_matches_reference(&signal, 1.0);
+            assert_matches_reference(&i16s, 32768.0);
+            assert_matches_reference(&i32s, 2_147_483_648.0);
+            assert_matches_reference(&i8s, 128.0);
+        }
+    }
+
+    #[test]
+    fn kernels_handle_full_scale_negative_samples() {
+        let samples = [i16::MIN, 0, 1, -1, i16::MAX];
+        let (peak, _) = levels(&samples, 32768.0);
+        assert_eq!(peak, 1.0);
+
+        let samples = [i32::MIN, 0, 0, 0, 0];
+        let (peak, _) = levels(&samples, 2_147_483_648.0);
+        assert_eq!(peak, 1.0);
+    }
+
+    #[test]
+    fn simd_and_scalar_kernels_agree() {
+        let signal = signal(5003);
+        let i16s: Vec<i16> = signal.iter().map(|x| (x * 32767.0) as i16).collect();
+
+        let (sum, peak) = i16::sum_squares_and_peak(&i16s);
+        let (scalar_sum, scalar_peak) = scalar(&i16s, |x| x as f32);
+
+        assert!((sum - scalar_sum).abs() <= scalar_sum * 1e-6);
+        assert_eq!(peak, scalar_peak);
+    }
+
+    #[test]
+    fn channel_and_mixdown_kernels_match_copying_the_samples_out() {
+        let signal = signal(3 * 2001);
+        let i16s: Vec<i16> = signal.iter().map(|x| (x * 32767.0) as i16).collect();
+
+        for index in 0..3 {
+            let channel: Vec<i16> = i16s.iter().skip(index).step_by(3).copied().collect();
+            assert_close(
+                channel_rms(&i16s, index, 3, 32768.0),
+                reference_rms(channel, 32768.0),
+            );
+        }
+
+        let mono: Vec<f32> = i16s
+            .chunks(3)
+            .map(|frame| frame.iter().map(|x| *x as f32).sum::<f32>() / 3.0)
+            .collect();
+        assert_close(mixdown_rms(&i16s, 3, 32768.0), reference_rms(mono, 32768.0));
+    }
+
+    #[test]
+    fn empty_slices_are_silent() {
+        assert_eq!(levels::<f32>(&[], 1.0), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,228 @@
+//! Reading WAV samples straight out of a memory-mapped file.
+//!
+//! When a file's samples are stored exactly as the sample type they're read as (16 or 32-bit
+//! integers, or 32-bit floats, on a little-endian machine), they're used where they sit in the
+//! mapped file instead of being decoded, and chunks are views into the mapping itself. Only the
+//! pages that are actually read ever get loaded.
+
+use std::{
+    io::Cursor,
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    ops::Deref,
+    sync::Arc,
+};
+
+use hound::{WavReader, WavSpec};
+use memmap2::Mmap;
+
+use crate::{
+    error::Error,
+    formats::wav::{SampleSource, WavSample},
+};
+
+/// A memory-mapped file, which can be shared between a reader and the chunks that view it.
+#[derive(Clone)]
+pub struct MappedFile {
+    map: Arc<Mmap>,
+}
+
+impl MappedFile {
+    pub(crate) fn new(map: Mmap) -> Self {
+        Self { map: Arc::new(map) }
+    }
+
+    /// Finds where the samples of the WAV file start, and how many there are.
+    pub(crate) fn data_chunk(&self) -> Result<DataChunk, Error> {
+        let mut cursor = Cursor::new(self.as_ref());
+        // Reading the header stops at the start of the samples in the `data` chunk.
+        let len = WavReader::new(&mut cursor)?.len() as usize;
+
+        Ok(DataChunk {
+            file: self.clone(),
+            start: cursor.position() as usize,
+            len,
+        })
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+/// Where the samples of a mapped WAV file are.
+pub(crate) struct DataChunk {
+    file: MappedFile,
+    /// The offset of the first sample, in bytes.
+    start: usize,
+    /// The number of samples.
+    len: usize,
+}
+
+impl DataChunk {
+    /// The samples as `T`, if they're stored in the file exactly as `T` would hold them.
+    pub(crate) fn samples<T>(&self, spec: &WavSpec) -> Option<MappedSamples<T>>
+    where
+        T: WavSample,
+    {
+        let width = size_of::<T>();
+        let end = self.len.checked_mul(width)?.checked_add(self.start)?;
+        // 8-bit WAV samples are unsigned, so they always need converting.
+        let usable = cfg!(target_endian = "little")
+            && width > 1
+            && spec.sample_format == T::SAMPLE_FORMAT
+            && spec.bits_per_sample as usize == width * 8
+            && end <= self.file.map.len()
+            && (self.file.map.as_ptr() as usize + self.start).is_multiple_of(align_of::<T>());
+
+        usable.then(|| MappedSamples {
+            file: self.file.clone(),
+            start: self.start,
+            len: self.len,
+            sample_type: PhantomData,
+        })
+    }
+}
+
+/// The samples of a mapped WAV file, viewed in place.
+#[derive(Clone)]
+pub(crate) struct MappedSamples<T> {
+    file: MappedFile,
+    start: usize,
+    len: usize,
+    sample_type: PhantomData<T>,
+}
+
+impl<T> Deref for MappedSamples<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        // SAFETY: These are only made by `DataChunk::samples`, which checks that the samples lie
+        // within the mapping, are aligned for `T` and are stored in `T`'s width and byte order.
+        // `T` is a `WavSample`, which (as `Kernel` can't be implemented outside this crate) is
+        // always a primitive integer or float, so any bytes are a valid value. The mapping is
+        // read-only and kept alive by `file`.
+        unsafe {
+            std::slice::from_raw_parts(self.file.map.as_ptr().add(self.start).cast(), self.len)
+        }
+    }
+}
+
+/// Reads samples from a mapped file by copying them, rather than decoding them.
+pub(crate) struct MappedReader<T> {
+    samples: MappedSamples<T>,
+    spec: WavSpec,
+    /// The next sample to read.
+    pos: usize,
+}
+
+impl<T> MappedReader<T> {
+    /// Reads `samples`, described by `spec`, starting at sample `pos`.
+    pub(crate) fn new(samples: MappedSamples<T>, spec: WavSpec, pos: usize) -> Self {
+        Self { samples, spec, pos }
+    }
+}
+
+impl<T> SampleSource<T> for MappedReader<T>
+where
+    T: WavSample,
+{
+    fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn remaining(&mut self) -> usize {
+        self.samples.len() - self.pos
+    }
+
+    fn read_into(&mut self, buffer: &mut Vec<T>, len: usize) -> Result<(), Error> {
+        let end = (self.pos + len).min(self.samples.len());
+        buffer.extend_from_slice(&self.samples[self.pos..end]);
+        self.pos = end;
+
+        Ok(())
+    }
+
+    fn seek(&mut self, frame: u32) -> Result<(), Error> {
+        self.pos = (frame as usize * self.spec.channels as usize).min(self.samples.len());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use hound::{SampleFormat, WavWriter};
+
+    use super::*;
+
+    fn mapped_wav(name: &str, spec: WavSpec, samples: &[i16]) -> MappedFile {
+        let path =
+            std::env::temp_dir().join(format!("dub-oxide-{name}-{}.wav", std::process::id()));
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let file = File::open(&path).unwrap();
+        // SAFETY: Nothing else touches this file, and it's unlinked while still mapped.
+        let map = unsafe { Mmap::map(&file).unwrap() };
+        std::fs::remove_file(path).unwrap();
+
+        MappedFile::new(map)
+    }
+
+    #[test]
+    fn samples_are_viewed_where_they_sit_in_the_file() {
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let file = mapped_wav("view", spec, &[1, -2, 3, -4, 5, -6]);
+        let data = file.data_chunk().unwrap();
+
+        let samples = data.samples::<i16>(&spec).unwrap();
+        assert_eq!(&samples[..], &[1, -2, 3, -4, 5, -6]);
+        assert_eq!(
+            samples.as_ptr().cast::<u8>(),
+            file.as_ref()[data.start..].as_ptr()
+        );
+
+        // Samples that would need widening are decoded instead.
+        assert!(data.samples::<i32>(&spec).is_none());
+    }
+
+    #[test]
+    fn reading_copies_from_the_current_position() {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let file = mapped_wav("read", spec, &[10, 20, 30, 40]);
+        let samples = file.data_chunk().unwrap().samples::<i16>(&spec).unwrap();
+        let mut reader = MappedReader::new(samples, spec, 1);
+
+        let mut buffer = Vec::new();
+        reader.read_into(&mut buffer, 2).unwrap();
+        assert_eq!(buffer, [20, 30]);
+        assert_eq!(reader.remaining(), 1);
+
+        reader.seek(0).unwrap();
+        reader.read_into(&mut buffer, 10).unwrap();
+        assert_eq!(buffer, [20, 30, 10, 20, 30, 40]);
+        assert_eq!(reader.remaining(), 0);
+    }
+}
@@ -1,5 +1,11 @@
 #[cfg(feature = "aiff")]
 pub mod aiff;
+#[cfg(feature = "async")]
+pub mod async_wav;
 
-mod common;
+pub(crate) mod common;
+pub(crate) mod kernel;
+#[cfg(feature = "mmap")]
+pub mod mapped;
+pub mod stems;
 pub mod wav;
@@ -0,0 +1,185 @@
+use std::{
+    collections::VecDeque,
+    io::{Read, Seek},
+};
+
+use hound::WavSpec;
+
+use crate::{
+    AudioSplitter, WavSplitter,
+    error::Error,
+    formats::wav::{SampleSource, WavSample, plan_chunks},
+    opts::SplitOpts,
+    plan::SplitPlan,
+    result::SplitResult,
+};
+
+/// Splits several WAV stems of the same recording, such as one per microphone, at the same
+/// frames.
+///
+/// Silence is looked for in the stems' combined signal, which has every channel of every stem in
+/// turn, so [`ChannelAnalysis`](crate::opts::ChannelAnalysis) decides how the stems are weighed
+/// against each other. The stems are read side by side while the cuts are worked out, so the
+/// combined signal is never held in memory.
+pub struct StemSplitter<R, T = i16> {
+    stems: Vec<WavSplitter<R, T>>,
+}
+
+impl<R, T> StemSplitter<R, T>
+where
+    R: Read + Seek,
+    T: WavSample,
+{
+    /// Returns an error unless every stem has the same sample rate, sample format and length.
+    /// Stems may have different numbers of channels.
+    pub fn new(stems: Vec<WavSplitter<R, T>>) -> Result<Self, Error> {
+        let Some(first) = stems.first() else {
+            return Err(Error::mismatched_stems("at least one stem is needed"));
+        };
+        let spec = first.codec();
+        let frames = first.reader.duration();
+
+        for stem in &stems[1..] {
+            let other = stem.codec();
+            if other.sample_rate != spec.sample_rate {
+                return Err(Error::mismatched_stems(&format!(
+                    "sample rates differ ({}Hz and {}Hz)",
+                    spec.sample_rate, other.sample_rate
+                )));
+            }
+            if other.sample_format != spec.sample_format
+                || other.bits_per_sample != spec.bits_per_sample
+            {
+                return Err(Error::mismatched_stems("sample formats differ"));
+            }
+            if stem.reader.duration() != frames {
+                return Err(Error::mismatched_stems(&format!(
+                    "lengths differ ({frames} and {} frames)",
+                    stem.reader.duration()
+                )));
+            }
+        }
+
+        Ok(Self { stems })
+    }
+
+    /// The format of the combined signal, which has the channels of every stem. Split options
+    /// should be built with this, rather than the format of any one stem.
+    pub fn codec(&self) -> WavSpec {
+        WavSpec {
+            channels: self.stems.iter().map(|x| x.codec().channels).sum(),
+            ..self.stems[0].codec()
+        }
+    }
+
+    pub fn stems(&self) -> &[WavSplitter<R, T>] {
+        &self.stems
+    }
+
+    pub fn into_stems(self) -> Vec<WavSplitter<R, T>> {
+        self.stems
+    }
+
+    /// Splits every stem at the same frames, returning one result per stem in the order the
+    /// stems were given.
+    pub fn split_audio(&mut self, opts: SplitOpts) -> Result<Vec<SplitResult<T, WavSpec>>, Error> {
+        let plan = self.plan(opts)?;
+
+        self.apply(plan)
+    }
+
+    /// Works out where the stems would be cut, from their combined signal.
+    pub fn plan(&mut self, opts: SplitOpts) -> Result<SplitPlan, Error> {
+        let spec = self.codec();
+        for stem in &mut self.stems {
+            stem.reset()?;
+        }
+
+        plan_chunks::<_, T>(&mut Interleaved::new(&mut self.stems, spec), opts)
+    }
+
+    /// Extracts the audio for each chunk in `plan` from every stem.
+    pub fn apply(&mut self, plan: SplitPlan) -> Result<Vec<SplitResult<T, WavSpec>>, Error> {
+        self.stems
+            .iter_mut()
+            .map(|stem| stem.apply(plan.clone()))
+            .collect()
+    }
+}
+
+/// The stems' combined signal, read a frame at a time with every channel of every stem in turn.
+struct Interleaved<'a, R, T> {
+    stems: &'a mut [WavSplitter<R, T>],
+    spec: WavSpec,
+    /// The part of the current frame that hasn't been read yet.
+    frame: VecDeque<T>,
+}
+
+impl<'a, R, T> Interleaved<'a, R, T>
+where
+    R: Read + Seek,
+    T: WavSample,
+{
+    fn new(stems: &'a mut [WavSplitter<R, T>], spec: WavSpec) -> Self {
+        Self {
+            stems,
+            spec,
+            frame: VecDeque::with_capacity(spec.channels as usize),
+        }
+    }
+
+    /// Reads the next frame of every stem, returning `false` once they've run out.
+    fn next_frame(&mut self) -> Result<bool, Error> {
+        for stem in self.stems.iter_mut() {
+            let channels = stem.codec().channels as usize;
+            for sample in stem.reader.samples::<T>().take(channels) {
+                self.frame.push_back(sample?);
+            }
+        }
+
+        Ok(!self.frame.is_empty())
+    }
+}
+
+impl<R, T> SampleSource<T> for Interleaved<'_, R, T>
+where
+    R: Read + Seek,
+    T: WavSample,
+{
+    fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    fn len(&self) -> usize {
+        self.stems[0].reader.duration() as usize * self.spec.channels as usize
+    }
+
+    fn remaining(&mut self) -> usize {
+        // Every stem is the same length, so the first says how many frames are left.
+        let stem = &mut self.stems[0];
+        let channels = stem.codec().channels as usize;
+        let frames = stem.reader.samples::<T>().len() / channels;
+
+        frames * self.spec.channels as usize + self.frame.len()
+    }
+
+    fn read_into(&mut self, buffer: &mut Vec<T>, len: usize) -> Result<(), Error> {
+        for _ in 0..len {
+            if self.frame.is_empty() && !self.next_frame()? {
+                break;
+            }
+            buffer.extend(self.frame.pop_front());
+        }
+
+        Ok(())
+    }
+
+    fn seek(&mut self, frame: u32) -> Result<(), Error> {
+        for stem in self.stems.iter_mut() {
+            stem.reader.seek(frame)?;
+        }
+        self.frame.clear();
+
+        Ok(())
+    }
+}
@@ -1,22 +1,89 @@
 use std::{
     fs::File,
     io::{BufReader, Cursor, Read, Seek},
+    marker::PhantomData,
+    ops::Range,
     path::Path,
+    sync::Arc,
+    time::Duration,
 };
 
+#[cfg(feature = "mmap")]
+use crate::formats::mapped::{DataChunk, MappedFile, MappedReader, MappedSamples};
 use crate::{
     BytesPerMillisecond,
     error::Error,
-    formats::common::{bytes_to_timestamp, find_silent_position},
-    result::SplitResult,
+    formats::{
+        common::{
+            SilenceDetector, align_to_frame, dbfs, estimate_threshold, levels, part_boundary,
+            shared_buffer,
+        },
+        kernel::Kernel,
+    },
+    opts::{CutPolicy, SilentChunks, SplitMode},
+    plan::{PlannedChunk, SplitPlan},
+    result::{CutReason, Samples, SplitResult},
 };
 
-use hound::{WavReader, WavSpec};
+use hound::{SampleFormat, WavReader, WavSpec};
+use num::{NumCast, ToPrimitive};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::{AudioChunk, AudioSplitter, SplitOpts};
 
-pub struct WavSplitter<R> {
-    reader: WavReader<R>,
+/// A sample type that WAV audio can be decoded into.
+///
+/// Integer files can be read into any integer type at least as wide as their bit depth (so a
+/// 24-bit file is read into `i32`), while IEEE float files are read into `f32`.
+pub trait WavSample:
+    hound::Sample + Kernel + num::Num + NumCast + ToPrimitive + Copy + Default + PartialEq + Send + Sync
+{
+    /// The sample format this type holds.
+    const SAMPLE_FORMAT: SampleFormat;
+    /// The widest bit depth this type can hold.
+    const MAX_BITS: u16;
+}
+
+impl WavSample for i8 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Int;
+    const MAX_BITS: u16 = 8;
+}
+
+impl WavSample for i16 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Int;
+    const MAX_BITS: u16 = 16;
+}
+
+impl WavSample for i32 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Int;
+    const MAX_BITS: u16 = 32;
+}
+
+impl WavSample for f32 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::Float;
+    const MAX_BITS: u16 = 32;
+}
+
+/// The value of a full-scale sample for the given spec.
+pub(crate) fn full_scale(spec: &WavSpec) -> f32 {
+    match spec.sample_format {
+        SampleFormat::Float => 1.0,
+        SampleFormat::Int => (1u64 << (spec.bits_per_sample.clamp(1, 64) - 1)) as f32,
+    }
+}
+
+/// Splits WAV audio into chunks.
+///
+/// Samples are decoded as `T`, which defaults to `i16`. Files with a different bit depth or
+/// sample format can be read by picking a matching type with [`WavSplitter::with_sample_type`];
+/// the decoded chunks keep the file's original bit depth.
+pub struct WavSplitter<R, T = i16> {
+    pub(crate) reader: WavReader<R>,
+    /// Where the samples are, if the file is memory-mapped.
+    #[cfg(feature = "mmap")]
+    data: Option<DataChunk>,
+    sample_type: PhantomData<T>,
 }
 
 impl WavSplitter<BufReader<File>> {
@@ -26,11 +93,43 @@ impl WavSplitter<BufReader<File>> {
     {
         let reader = WavReader::open(path)?;
 
-        Ok(Self { reader })
+        Ok(Self::from_reader(reader))
     }
+}
 
-    pub fn codec(&self) -> WavSpec {
-        self.reader.spec()
+#[cfg(feature = "mmap")]
+impl WavSplitter<Cursor<MappedFile>> {
+    /// Opens the WAV file at `path` by mapping it into memory rather than reading it.
+    ///
+    /// Samples are read straight out of the mapped file, so only the parts that silence scanning
+    /// and chunk extraction touch are ever paged in. This suits very large local files.
+    ///
+    /// When the samples are stored exactly as the sample type (16-bit integer files read as
+    /// `i16`, or 32-bit files read as `i32` or `f32` with [`WavSplitter::with_sample_type`]),
+    /// they aren't even decoded, and [`split_audio`](AudioSplitter::split_audio) and
+    /// [`apply`](AudioSplitter::apply) don't copy them: the chunks are views into the mapped
+    /// file, which stays mapped until the last of them is dropped.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or any other, while it's
+    /// mapped: that is, until both the splitter and every chunk it produced have been dropped.
+    /// Chunks read the mapped memory directly, so a change to the file would change audio that's
+    /// meant to be immutable, and reading past the end of a truncated file crashes the process.
+    pub unsafe fn from_mmap<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path)?;
+        // SAFETY: The caller promises the file won't change while it's mapped.
+        let file = MappedFile::new(unsafe { memmap2::Mmap::map(&file)? });
+        let data = file.data_chunk()?;
+        let reader = WavReader::new(Cursor::new(file))?;
+
+        Ok(Self {
+            data: Some(data),
+            ..Self::from_reader(reader)
+        })
     }
 }
 
@@ -38,37 +137,76 @@ impl<'a> WavSplitter<Cursor<&'a [u8]>> {
     pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, Error> {
         let reader = WavReader::new(Cursor::new(bytes))?;
 
-        Ok(Self { reader })
+        Ok(Self::from_reader(reader))
+    }
+}
+
+impl<R, T> WavSplitter<R, T>
+where
+    R: Read,
+{
+    fn from_reader(reader: WavReader<R>) -> Self {
+        Self {
+            reader,
+            #[cfg(feature = "mmap")]
+            data: None,
+            sample_type: PhantomData,
+        }
     }
 
     pub fn codec(&self) -> WavSpec {
         self.reader.spec()
     }
+
+    /// Decodes samples as `U` instead, keeping the file's original bit depth.
+    ///
+    /// Returns an error if the file's samples can't be represented by `U`, for example a 24-bit
+    /// file read as `i16` or an integer file read as `f32`.
+    pub fn with_sample_type<U>(self) -> Result<WavSplitter<R, U>, Error>
+    where
+        U: WavSample,
+    {
+        check_sample_type::<U>(&self.reader.spec())?;
+
+        Ok(WavSplitter {
+            reader: self.reader,
+            #[cfg(feature = "mmap")]
+            data: self.data,
+            sample_type: PhantomData,
+        })
+    }
 }
 
-impl<R> WavSplitter<R>
+/// Returns an error if samples described by `spec` can't be decoded as `U`.
+pub(crate) fn check_sample_type<U>(spec: &WavSpec) -> Result<(), Error>
 where
-    R: Read + Seek,
+    U: WavSample,
 {
-    fn get_bytes(&mut self) -> Result<Vec<i16>, Error> {
-        let samples: Vec<i16> = self
-            .reader
-            .samples::<i16>()
-            .filter_map(|x| x.ok())
-            .collect();
-        let samples_len = samples.len() as u32;
-
-        if samples_len != self.reader.len() {
-            return Err(Error::inconsistent_byte_length(
-                samples.len(),
-                self.reader.len() as usize,
-            ));
-        }
+    if spec.sample_format != U::SAMPLE_FORMAT || spec.bits_per_sample > U::MAX_BITS {
+        return Err(Error::incompatible_sample_format(
+            spec.sample_format,
+            spec.bits_per_sample,
+            std::any::type_name::<U>(),
+        ));
+    }
 
-        #[cfg(feature = "tracing")]
-        tracing::trace!("{samples_len} samples loaded.");
+    Ok(())
+}
 
-        Ok(samples)
+impl<R, T> WavSplitter<R, T>
+where
+    R: Read + Seek,
+    T: WavSample,
+{
+    /// Lazily splits the audio into chunks.
+    ///
+    /// Samples are only read from the underlying reader as far as the next cut needs, so memory
+    /// usage stays at roughly one chunk plus the silence search window regardless of file length.
+    pub fn chunks(
+        &mut self,
+        opts: SplitOpts,
+    ) -> impl Iterator<Item = Result<AudioChunk<T>, Error>> + '_ {
+        WavChunks::new(&mut self.reader, opts)
     }
 
     pub fn reset(&mut self) -> Result<(), Error> {
@@ -76,89 +214,755 @@ where
 
         Ok(())
     }
+
+    /// The samples where they sit in the mapped file, if they can be used without decoding.
+    #[cfg(feature = "mmap")]
+    fn mapped_samples(&self) -> Option<MappedSamples<T>> {
+        self.data.as_ref()?.samples(&self.reader.spec())
+    }
 }
 
-impl<R> AudioSplitter for WavSplitter<R>
+impl<R, T> AudioSplitter for WavSplitter<R, T>
 where
     R: Read + Seek,
+    T: WavSample,
 {
-    type ByteSize = i16;
+    type ByteSize = T;
     type CodecParams = WavSpec;
 
+    /// Works out every cut first, then reads the audio they cover into a single buffer that the
+    /// chunks are views into.
     fn split_audio(
         &mut self,
         opts: SplitOpts,
     ) -> Result<SplitResult<Self::ByteSize, Self::CodecParams>, Error> {
-        let byte_limit = opts.frame_size();
-        let bytes_per_ms = self.reader.spec().bytes_per_ms() as usize;
+        let plan = self.plan(opts)?;
 
-        let mut bigvec = Vec::new();
+        self.apply(plan)
+    }
 
-        let mut offset: usize = 0;
+    fn plan(&mut self, opts: SplitOpts) -> Result<SplitPlan, Error> {
+        #[cfg(feature = "mmap")]
+        if let Some(samples) = self.mapped_samples() {
+            let pos = self.reader.len() as usize - self.reader.samples::<T>().len();
+            let mut source = MappedReader::new(samples, self.reader.spec(), pos);
+            let plan = plan_chunks(&mut source, opts)?;
+            // Leave the reader where planning left off, as if it had been read.
+            let frame = (source.len() - source.remaining()) / self.reader.spec().channels as usize;
+            self.reader.seek(frame as u32)?;
+
+            return Ok(plan);
+        }
 
-        let bytes = self.get_bytes()?;
+        plan_chunks::<_, T>(&mut self.reader, opts)
+    }
 
-        #[cfg(feature = "tracing")]
-        tracing::trace!("Bytes length:{}", bytes.len());
+    /// Reads the audio the plan covers into a single buffer, which every chunk is a view into. A
+    /// memory-mapped file's samples are used where they sit instead, when they can be.
+    fn apply(
+        &mut self,
+        plan: SplitPlan,
+    ) -> Result<SplitResult<Self::ByteSize, Self::CodecParams>, Error> {
+        let spec = self.reader.spec();
+        let channels = spec.channels as usize;
+        let full_scale = full_scale(&spec);
+        let frames = self.reader.duration() as usize;
+        let threshold = plan.silence_threshold();
+
+        if plan.iter().any(|planned| planned.end_frame() > frames) {
+            return Err(Error::invalid_option(
+                "plan",
+                "has a chunk that runs past the end of the audio",
+            ));
+        }
 
+        let start = plan
+            .iter()
+            .map(|x| x.start_frame())
+            .min()
+            .unwrap_or_default();
+        let end = plan.iter().map(|x| x.end_frame()).max().unwrap_or_default();
+
+        // A mapped file's samples are used where they are, so chunks are views into the file.
+        #[cfg(feature = "mmap")]
+        let mapped = self.mapped_samples().map(Samples::from);
+        #[cfg(not(feature = "mmap"))]
+        let mapped = None;
+
+        let (start, buffer) = match mapped {
+            Some(samples) => (0, samples),
+            None => {
+                self.reader.seek(start as u32)?;
+                let mut buffer = shared_buffer::<T>((end - start) * channels);
+                let slots = Arc::get_mut(&mut buffer).expect("a new buffer isn't shared yet");
+                let mut samples = self.reader.samples::<T>();
+                for slot in slots {
+                    let Some(sample) = samples.next() else {
+                        return Err(Error::invalid_option(
+                            "plan",
+                            "has a chunk that runs past the end of the audio",
+                        ));
+                    };
+                    *slot = sample?;
+                }
+
+                (start, Samples::from(buffer))
+            }
+        };
+
+        let planned: Vec<PlannedChunk> = plan.into_iter().collect();
+
+        // Measuring each chunk's levels is the only real work left, so it's spread across threads.
+        #[cfg(feature = "parallel")]
+        let planned = planned.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let planned = planned.into_iter();
+
+        let bigvec = planned
+            .enumerate()
+            .map(|(index, planned)| {
+                let range = (planned.start_frame() - start) * channels
+                    ..(planned.end_frame() - start) * channels;
+                extract_chunk(planned, buffer.clone(), range, index, channels, full_scale)
+            })
+            .collect();
+
+        Ok(SplitResult::new(bigvec, spec).with_silence_threshold(threshold))
+    }
+}
+
+/// Builds the chunk `planned` describes as a view of `range` of `buffer`, and measures its levels.
+fn extract_chunk<T>(
+    planned: PlannedChunk,
+    buffer: Samples<T>,
+    range: Range<usize>,
+    index: usize,
+    channels: usize,
+    full_scale: f32,
+) -> AudioChunk<T>
+where
+    T: WavSample,
+{
+    let (peak, rms) = levels(&buffer[range.clone()], full_scale);
+
+    planned
+        .to_chunk(buffer, range, channels, index)
+        .with_levels(peak, rms)
+}
+
+/// Where [`WavChunks`] reads its samples from, either a WAV file or something that produces
+/// samples the same way.
+pub(crate) trait SampleSource<T> {
+    fn spec(&self) -> WavSpec;
+
+    /// The total number of samples.
+    fn len(&self) -> usize;
+
+    /// The number of samples that haven't been read yet.
+    fn remaining(&mut self) -> usize;
+
+    /// Reads up to `len` more samples onto the end of `buffer`.
+    fn read_into(&mut self, buffer: &mut Vec<T>, len: usize) -> Result<(), Error>;
+
+    /// Moves to the start of frame `frame`.
+    fn seek(&mut self, frame: u32) -> Result<(), Error>;
+}
+
+impl<R, T> SampleSource<T> for WavReader<R>
+where
+    R: Read + Seek,
+    T: WavSample,
+{
+    fn spec(&self) -> WavSpec {
+        WavReader::spec(self)
+    }
+
+    fn len(&self) -> usize {
+        WavReader::len(self) as usize
+    }
+
+    fn remaining(&mut self) -> usize {
+        self.samples::<T>().len()
+    }
+
+    fn read_into(&mut self, buffer: &mut Vec<T>, len: usize) -> Result<(), Error> {
+        for sample in self.samples::<T>().take(len) {
+            buffer.push(sample?);
+        }
+
+        Ok(())
+    }
+
+    fn seek(&mut self, frame: u32) -> Result<(), Error> {
+        WavReader::seek(self, frame)?;
+
+        Ok(())
+    }
+}
+
+/// Works out every cut in the rest of `source`, without keeping any of its audio.
+pub(crate) fn plan_chunks<S, T>(source: &mut S, opts: SplitOpts) -> Result<SplitPlan, Error>
+where
+    S: SampleSource<T>,
+    T: WavSample,
+{
+    let mut chunks = WavChunks::<S, T>::new(source, opts);
+    let planned = std::iter::from_fn(|| chunks.next_with(|planned, _, _| planned))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(SplitPlan::new(planned).with_silence_threshold(chunks.threshold))
+}
+
+/// Streaming chunk iterator returned by [`WavSplitter::chunks`], and run by the async splitter.
+pub(crate) struct WavChunks<'a, S, T> {
+    reader: &'a mut S,
+    opts: SplitOpts,
+    sample_rate: u32,
+    channels: usize,
+    silence: SilenceDetector,
+    /// The silence threshold in use, once it's known.
+    threshold: Option<f32>,
+    /// Whether the silence threshold still needs to be picked from the audio.
+    needs_estimate: bool,
+    /// Samples that have been read but not yet handed out as part of a chunk.
+    buffer: Vec<T>,
+    /// Position the reader was at when splitting started.
+    start: usize,
+    /// Total number of samples in the file.
+    total: usize,
+    /// Position of the first buffered sample, relative to the start of the file.
+    offset: usize,
+    /// How many samples at the front of the buffer repeat the end of the previous chunk.
+    lead: usize,
+    /// Samples left in the reader that have not been buffered yet.
+    remaining: usize,
+    /// Number of chunks cut so far.
+    taken: usize,
+    /// Number of chunks handed out so far, leaving out any that were dropped for being silent.
+    yielded: usize,
+    /// Number of caller-supplied cut positions that have been dealt with.
+    cuts_used: usize,
+    finished: bool,
+}
+
+impl<'a, S, T> WavChunks<'a, S, T>
+where
+    S: SampleSource<T>,
+    T: WavSample,
+{
+    pub(crate) fn new(reader: &'a mut S, opts: SplitOpts) -> Self {
+        let sample_rate = reader.spec().sample_rate;
+        let channels = reader.spec().channels as usize;
+        let window = opts
+            .analysis_window()
+            .unwrap_or_else(|| reader.spec().samples_in(Duration::from_millis(50)));
+        let silence = SilenceDetector {
+            window,
+            hop: opts.analysis_hop().unwrap_or(window),
+            frame_width: channels,
+            full_scale: full_scale(&reader.spec()),
+            enter_threshold: opts.silence_threshold().unwrap_or(f32::NEG_INFINITY),
+            exit_threshold: opts.silence_exit_threshold().unwrap_or(f32::NEG_INFINITY),
+            min_len: opts.min_silence_len(),
+            vad: opts.voice_activity().copied(),
+            channels: opts.channel_analysis().clone(),
+        };
+        let remaining = reader.remaining();
+        let total = reader.len();
+        let offset = total - remaining;
+
+        Self {
+            reader,
+            sample_rate,
+            channels,
+            silence,
+            threshold: opts.silence_threshold(),
+            needs_estimate: opts.noise_floor_margin().is_some(),
+            opts,
+            buffer: Vec::new(),
+            start: offset,
+            total,
+            offset,
+            lead: 0,
+            remaining,
+            taken: 0,
+            yielded: 0,
+            cuts_used: 0,
+            finished: false,
+        }
+    }
+
+    /// Picks the silence threshold from the level of the rest of the file, then rewinds the reader
+    /// so splitting can start.
+    fn estimate_threshold(&mut self, margin: f32) -> Result<(), Error> {
+        let window = align_to_frame(self.silence.window, self.channels).max(self.channels);
+        let mut levels = Vec::new();
+        let mut samples = Vec::with_capacity(window);
         loop {
-            if offset >= bytes.len() {
+            samples.clear();
+            self.reader.read_into(&mut samples, window)?;
+            if samples.is_empty() {
                 break;
             }
-            if bytes[offset..bytes.len()].len() <= byte_limit {
-                let timestamp_start = bytes_to_timestamp(offset, bytes_per_ms);
-                let timestamp_end = bytes_to_timestamp(bytes.len(), bytes_per_ms);
+            levels.push(dbfs(self.silence.level(&samples)));
+        }
+
+        self.reader.seek((self.start / self.channels) as u32)?;
+        self.remaining = self.reader.remaining();
+
+        self.threshold = estimate_threshold(levels, margin);
+        let threshold = self.threshold.unwrap_or(f32::NEG_INFINITY);
+        self.silence.enter_threshold = threshold;
+        self.silence.exit_threshold = threshold;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!("Picked a silence threshold of {threshold}dBFS");
+
+        Ok(())
+    }
+
+    /// Tops the buffer up to `len` samples, or until the reader runs out.
+    fn fill(&mut self, len: usize) -> Result<(), Error> {
+        let wanted = len.saturating_sub(self.buffer.len()).min(self.remaining);
+        if wanted == 0 {
+            return Ok(());
+        }
+
+        self.buffer.reserve(wanted);
+        self.reader.read_into(&mut self.buffer, wanted)?;
+
+        // If the reader stops short we'd otherwise keep asking it for samples that aren't there.
+        self.remaining = self.reader.remaining();
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!("{wanted} samples loaded.");
+
+        Ok(())
+    }
+
+    /// Plans the chunk made of the buffered samples in `range`, handing the plan and its samples
+    /// to `extract`, then drops everything before the end of `range` apart from the overlap that
+    /// the next chunk will start with.
+    fn take_chunk<U>(
+        &mut self,
+        range: Range<usize>,
+        reason: CutReason,
+        extract: impl FnOnce(PlannedChunk, &[T]) -> U,
+    ) -> U {
+        let cut_end = range.end;
+        let check_silent = self.opts.silent_chunks() != SilentChunks::Keep;
+        let analyse = self.opts.trim_silence().is_some() || check_silent;
+        let audible = analyse.then(|| self.silence.audible_range(&self.buffer[range.clone()]));
+        let silent = check_silent && matches!(audible, Some(None));
+        // A chunk that's silent throughout has nothing audible to keep silence around, so it's
+        // trimmed as though its audio were empty and at its very start.
+        let Range { start, end } = match audible {
+            Some(audible) => self.trim(range, audible.unwrap_or_default()),
+            None => range,
+        };
+        let start_frame = (self.offset + start) / self.channels;
+        let core_start = (self.offset + self.lead.max(start)) / self.channels;
+        let end_frame = (self.offset + end) / self.channels;
+
+        let planned = PlannedChunk::new(self.sample_rate, start_frame, end_frame)
+            .with_core_range(core_start, end_frame)
+            .with_cut_reason(reason)
+            .with_silent(silent)
+            .with_cut_level(
+                (reason != CutReason::EndOfInput)
+                    .then(|| self.silence.level_around(&self.buffer, cut_end)),
+            );
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            "Created chunk at timestamp {:?} to {:?}",
+            planned.start(),
+            planned.end()
+        );
+
+        let extracted = extract(planned, &self.buffer[start..end]);
+
+        self.lead = align_to_frame(self.opts.overlap(), self.channels).min(end - start);
+        self.buffer.drain(..cut_end - self.lead);
+        self.offset += cut_end - self.lead;
+        self.taken += 1;
+
+        extracted
+    }
+
+    /// Cuts the next chunk that isn't being dropped, handing it to `extract` along with its
+    /// samples and its index in the results.
+    fn next_with<U>(
+        &mut self,
+        mut extract: impl FnMut(PlannedChunk, &[T], usize) -> U,
+    ) -> Option<Result<U, Error>> {
+        while !self.finished {
+            match self.next_cut() {
+                Ok(Some((range, reason))) => {
+                    let drop_silent = self.opts.silent_chunks() == SilentChunks::Drop;
+                    let index = self.yielded;
+                    let extracted = self.take_chunk(range, reason, |planned, data| {
+                        (!(drop_silent && planned.is_silent()))
+                            .then(|| extract(planned, data, index))
+                    });
+
+                    let Some(extracted) = extracted else {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("Dropped silent chunk");
+                        continue;
+                    };
+
+                    self.yielded += 1;
+                    return Some(Ok(extracted));
+                }
+                Ok(None) => self.finished = true,
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        None
+    }
 
-                let audio_chunk =
-                    AudioChunk::new(&bytes[offset..bytes.len()], timestamp_start, timestamp_end);
+    /// Shrinks `range` so no more than the configured amount of silence is left either side of
+    /// `audible`, the part of it that isn't silent.
+    fn trim(&self, range: Range<usize>, audible: Range<usize>) -> Range<usize> {
+        let Some(max_keep) = self.opts.trim_silence() else {
+            return range;
+        };
 
-                bigvec.push(audio_chunk);
+        let max_keep = align_to_frame(max_keep, self.channels);
+        let start = range.start + audible.start.saturating_sub(max_keep);
+        let end = (range.start + audible.end + max_keep).min(range.end);
 
+        start..end
+    }
+
+    /// Works out which part of the buffer makes up the next chunk, and what decided where it ends.
+    ///
+    /// Returns `None` once there is nothing left to split.
+    fn next_cut(&mut self) -> Result<Option<(Range<usize>, CutReason)>, Error> {
+        if let Some(margin) = self.opts.noise_floor_margin()
+            && self.needs_estimate
+        {
+            self.needs_estimate = false;
+            self.estimate_threshold(margin)?;
+        }
+
+        let cut = match *self.opts.mode() {
+            SplitMode::Size(size) => self.next_sized_cut(size)?.map(|(pos, x)| (0..pos, x)),
+            SplitMode::Parts(parts) => self.next_part_cut(parts)?.map(|(pos, x)| (0..pos, x)),
+            SplitMode::Pauses(min_pause) => self.next_region(min_pause)?,
+            SplitMode::Timestamps(_) => self.next_timestamp_cut()?.map(|(pos, x)| (0..pos, x)),
+        };
+
+        Ok(cut)
+    }
+
+    /// The shortest and longest chunk allowed, relative to the start of the buffer.
+    fn bounds(&self, default_max: usize) -> (usize, usize) {
+        let min_len =
+            align_to_frame(self.opts.min_len().unwrap_or_default(), self.channels).max(self.lead);
+        let max_len = self
+            .opts
+            .max_len()
+            .map(|x| align_to_frame(x, self.channels))
+            .unwrap_or(default_max)
+            .max(min_len)
+            // A chunk always gets at least one frame past the audio it shares with the last one.
+            .max(self.lead + self.channels);
+
+        (min_len, max_len)
+    }
+
+    /// Hands out everything that's left as the final chunk.
+    fn rest(&mut self) -> Result<Option<(usize, CutReason)>, Error> {
+        self.fill(self.buffer.len() + self.remaining)?;
+
+        Ok((self.buffer.len() > self.lead).then_some((self.buffer.len(), CutReason::EndOfInput)))
+    }
+
+    /// Searches `range` of the buffer for somewhere to cut with the configured [`CutPolicy`],
+    /// aiming for `target`.
+    fn search(&self, range: Range<usize>, target: usize) -> Option<(usize, CutReason)> {
+        let policy = self.opts.cut_policy();
+        if self.threshold.is_none()
+            && self.silence.vad.is_none()
+            && policy != CutPolicy::QuietestWindow
+        {
+            return None;
+        }
+
+        if range.is_empty() {
+            return None;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            "Searching for a chunk between position {} and position {}",
+            self.offset + range.start,
+            self.offset + range.end
+        );
+
+        let pos = self
+            .silence
+            .find_cut(&self.buffer, range.clone(), target, policy, self.taken > 0)
+            // A cut at the very start of the chunk would make it empty and never make progress.
+            .filter(|pos| *pos > self.lead);
+
+        #[cfg(feature = "tracing")]
+        if pos.is_none() {
+            tracing::info!(
+                "Could not find chunk between {} and {}",
+                self.offset + range.start,
+                self.offset + range.end
+            );
+        }
+
+        pos.map(|pos| (pos, CutReason::Policy(policy)))
+    }
+
+    fn next_sized_cut(&mut self, size: usize) -> Result<Option<(usize, CutReason)>, Error> {
+        // Never cut inside a frame, otherwise every following chunk would have its channels
+        // swapped around.
+        let byte_limit = align_to_frame(size, self.channels).max(self.channels);
+        let lead = self.lead;
+        let (min_len, max_len) = self.bounds(lead + byte_limit);
+        let target = (lead + byte_limit).clamp(min_len.max(lead + self.channels), max_len);
+
+        self.fill(max_len)?;
+
+        if self.buffer.len() <= lead {
+            return Ok(None);
+        }
+
+        if self.remaining == 0 && self.buffer.len() <= max_len {
+            return Ok(Some((self.buffer.len(), CutReason::EndOfInput)));
+        }
+
+        let cut = self
+            .search(min_len..max_len, target)
+            .unwrap_or((target, CutReason::Target));
+
+        Ok(Some(cut))
+    }
+
+    fn next_part_cut(&mut self, parts: usize) -> Result<Option<(usize, CutReason)>, Error> {
+        if self.taken >= parts {
+            return Ok(None);
+        }
+
+        // The last part takes whatever is left over.
+        if self.taken + 1 == parts {
+            return self.rest();
+        }
+
+        let (min_len, max_len) = self.bounds(usize::MAX);
+        // Every part gets at least one frame, even when there are more parts than frames.
+        let min_len = min_len.max(self.lead + self.channels);
+        let boundary = part_boundary(self.start, self.total, parts, self.taken, self.channels);
+        let ideal = boundary.saturating_sub(self.offset).clamp(min_len, max_len);
+        // Pauses up to a quarter of a part either side of the ideal cut are considered.
+        let tolerance = align_to_frame((self.total - self.start) / parts / 4, self.channels);
+
+        self.fill(ideal + tolerance)?;
+
+        // Shorter audio than that runs out of frames before it runs out of parts.
+        if self.buffer.len() <= self.lead {
+            return Ok(None);
+        }
+        let ideal = ideal.min(self.buffer.len());
+
+        let search_start = ideal.saturating_sub(tolerance).max(min_len);
+        let search_end = (ideal + tolerance).min(max_len).min(self.buffer.len());
+        let cut = self
+            .search(search_start..search_end, ideal)
+            .unwrap_or((ideal, CutReason::Target));
+
+        Ok(Some(cut))
+    }
+
+    fn next_timestamp_cut(&mut self) -> Result<Option<(usize, CutReason)>, Error> {
+        let SplitMode::Timestamps(cuts) = self.opts.mode() else {
+            unreachable!("only called when splitting at timestamps");
+        };
+
+        // Skip over any cuts that an earlier cut has already been snapped past.
+        let core_start = self.offset + self.lead;
+        let next = cuts[self.cuts_used..]
+            .iter()
+            .position(|cut| align_to_frame(*cut, self.channels) > core_start)
+            .map(|i| self.cuts_used + i);
+
+        let Some(index) = next else {
+            self.cuts_used = cuts.len();
+
+            return self.rest();
+        };
+
+        let cut_count = cuts.len();
+        let ideal = align_to_frame(cuts[index], self.channels) - self.offset;
+        self.cuts_used = index + 1;
+        let tolerance = align_to_frame(
+            self.opts.snap_tolerance().unwrap_or_default(),
+            self.channels,
+        );
+
+        self.fill(ideal + tolerance)?;
+
+        // The file ends before this cut, so whatever's left is the last chunk.
+        if self.buffer.len() <= ideal && self.remaining == 0 {
+            self.cuts_used = cut_count;
+
+            return self.rest();
+        }
+
+        if tolerance == 0 {
+            return Ok(Some((ideal, CutReason::Target)));
+        }
+
+        let search_start = ideal.saturating_sub(tolerance).max(self.lead);
+        let search_end = (ideal + tolerance).min(self.buffer.len());
+        let cut = self
+            .search(search_start..search_end, ideal)
+            .unwrap_or((ideal, CutReason::Target));
+
+        Ok(Some(cut))
+    }
+
+    /// Finds the next non-silent region, ending at the first pause of at least `min_pause`
+    /// samples (or at the longest allowed chunk length). Pauses before the shortest allowed chunk
+    /// length are kept as part of the region.
+    fn next_region(
+        &mut self,
+        min_pause: usize,
+    ) -> Result<Option<(Range<usize>, CutReason)>, Error> {
+        let SilenceDetector {
+            window,
+            hop,
+            enter_threshold,
+            exit_threshold,
+            ..
+        } = self.silence;
+        let min_pause = min_pause.max(self.silence.min_len);
+        let padding = align_to_frame(self.opts.padding(), self.channels);
+
+        // Skip over the silence before the region, only holding on to as much of it as will be
+        // kept as padding.
+        let mut cursor = 0;
+        let mut threshold = enter_threshold;
+        loop {
+            self.fill(cursor + window)?;
+            if cursor >= self.buffer.len() {
+                return Ok(None);
+            }
+
+            let end = (cursor + window).min(self.buffer.len());
+            if !self.silence.is_silent(&self.buffer[cursor..end], threshold) {
                 break;
             }
 
-            let end_pos = if offset + byte_limit >= bytes.len() {
-                bytes.len()
-            } else {
-                offset + byte_limit
-            };
+            // Once in a pause, it lasts until the level rises above the exit threshold.
+            threshold = exit_threshold;
 
-            let pos = if let Some(threshold) = opts.silence_threshold() {
-                #[cfg(feature = "tracing")]
-                tracing::trace!(
-                    "Searching for a chunk between position {offset} and position {end_pos}"
-                );
-
-                let pos = if let Some(pos) = find_silent_position::<_, Self>(
-                    &bytes[offset..end_pos],
-                    bytes_per_ms * 50,
-                    threshold,
-                ) {
-                    pos + offset
-                } else {
-                    #[cfg(feature = "tracing")]
-                    tracing::info!("Could not find chunk between X and X");
-
-                    offset + byte_limit
-                };
-                pos
-            } else {
-                offset + byte_limit
-            };
+            cursor = (cursor + hop).min(end);
+            let excess = cursor.saturating_sub(padding);
+            self.buffer.drain(..excess);
+            self.offset += excess;
+            cursor -= excess;
+        }
 
-            let timestamp_start = bytes_to_timestamp(offset, bytes_per_ms);
-            let timestamp_end = bytes_to_timestamp(pos, bytes_per_ms);
+        let start = cursor.saturating_sub(padding);
+        let min_len = align_to_frame(self.opts.min_len().unwrap_or_default(), self.channels);
+        let max_len = self
+            .opts
+            .max_len()
+            .map(|x| align_to_frame(x, self.channels));
+        let mut pause_start = None;
 
-            let audiochunk = AudioChunk::new(&bytes[offset..pos], timestamp_start, timestamp_end);
+        loop {
+            if let Some(max_len) = max_len
+                && cursor - start >= max_len.max(self.channels)
+            {
+                return Ok(Some((
+                    start..start + max_len.max(self.channels),
+                    CutReason::Target,
+                )));
+            }
 
-            #[cfg(feature = "tracing")]
-            tracing::debug!("Created chunk at timestamp {timestamp_start}ms to {timestamp_end}ms");
+            self.fill(cursor + window)?;
+            if cursor >= self.buffer.len() {
+                let end = pause_start.unwrap_or(cursor);
+                return Ok(Some((
+                    start..(end + padding).min(cursor),
+                    CutReason::EndOfInput,
+                )));
+            }
 
-            bigvec.push(audiochunk);
-            offset = pos;
+            let end = (cursor + window).min(self.buffer.len());
+            let threshold = if pause_start.is_some() {
+                exit_threshold
+            } else {
+                enter_threshold
+            };
+            if !self.silence.is_silent(&self.buffer[cursor..end], threshold) {
+                pause_start = None;
+                cursor = (cursor + hop).min(end);
+                continue;
+            }
+
+            let pause = *pause_start.get_or_insert(cursor);
+            // The end of the last silent window in the pause.
+            let mut pause_end = end;
+            cursor = (cursor + hop).min(end);
+
+            if pause_end - pause >= min_pause && (pause + padding).min(pause_end) - start >= min_len
+            {
+                // Extend the padding into the rest of the pause, if it's long enough.
+                while pause_end - pause < padding {
+                    self.fill(cursor + window)?;
+                    let end = (cursor + window).min(self.buffer.len());
+                    if cursor >= end
+                        || !self
+                            .silence
+                            .is_silent(&self.buffer[cursor..end], exit_threshold)
+                    {
+                        break;
+                    }
+                    pause_end = end;
+                    cursor = (cursor + hop).min(end);
+                }
+
+                return Ok(Some((
+                    start..(pause + padding).min(pause_end),
+                    CutReason::Pause,
+                )));
+            }
         }
+    }
+}
 
-        let split_result = SplitResult::new(bigvec, self.reader.spec());
-        Ok(split_result)
+impl<S, T> Iterator for WavChunks<'_, S, T>
+where
+    S: SampleSource<T>,
+    T: WavSample,
+{
+    type Item = Result<AudioChunk<T>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (channels, full_scale) = (self.channels, self.silence.full_scale);
+
+        self.next_with(|planned, data, index| {
+            extract_chunk(
+                planned,
+                Arc::<[T]>::from(data).into(),
+                0..data.len(),
+                index,
+                channels,
+                full_scale,
+            )
+        })
     }
 }
@@ -1,13 +1,19 @@
 pub mod error;
 pub mod formats;
 pub mod opts;
+pub mod plan;
 pub mod result;
+pub mod vad;
+
+use std::time::Duration;
 
 use error::Error;
+use formats::common::duration_to_frames;
 pub use formats::wav::WavSplitter;
 use hound::WavSpec;
 use num::ToPrimitive;
 use opts::SplitOpts;
+use plan::SplitPlan;
 pub use result::AudioChunk;
 use result::SplitResult;
 use symphonia::core::codecs::CodecParameters;
@@ -19,35 +25,63 @@ pub trait AudioSplitter {
         &mut self,
         opts: SplitOpts,
     ) -> Result<SplitResult<Self::ByteSize, Self::CodecParams>, Error>;
+
+    /// Works out where [`AudioSplitter::split_audio`] would cut, without building any chunks.
+    fn plan(&mut self, opts: SplitOpts) -> Result<SplitPlan, Error>;
+
+    /// Extracts the audio for each chunk in `plan`, which may have been adjusted since it was
+    /// made. Chunks are extracted in the order they appear in the plan.
+    fn apply(
+        &mut self,
+        plan: SplitPlan,
+    ) -> Result<SplitResult<Self::ByteSize, Self::CodecParams>, Error>;
 }
 
 pub trait BytesPerMillisecond {
-    fn bytes_per_ms(&self) -> usize;
+    /// The number of frames per second.
+    fn sample_rate(&self) -> u32;
+
+    /// The number of samples in each frame.
+    fn channels(&self) -> usize;
+
+    /// The number of samples per millisecond, rounded down.
+    ///
+    /// This is only approximate (44.1 kHz stereo gives 88 rather than 88.2), so prefer
+    /// [`BytesPerMillisecond::samples_in`] when converting durations.
+    fn bytes_per_ms(&self) -> usize {
+        (self.sample_rate() as usize * self.channels()) / 1000
+    }
+
+    /// The exact number of samples in `duration`, rounded to the nearest whole frame.
+    fn samples_in(&self, duration: Duration) -> usize {
+        duration_to_frames(duration, self.sample_rate()) * self.channels()
+    }
 }
 
 impl BytesPerMillisecond for WavSpec {
-    fn bytes_per_ms(&self) -> usize {
-        let sample_rate = self.sample_rate;
-        let channels = self.channels as u32;
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
 
-        // Calculate bytes per millisecond
-        ((sample_rate * channels) / 1000) as usize
+    fn channels(&self) -> usize {
+        self.channels as usize
     }
 }
 
 impl BytesPerMillisecond for CodecParameters {
-    fn bytes_per_ms(&self) -> usize {
-        let sample_rate = self.sample_rate.unwrap();
-        let channels = self.channels.unwrap().count();
-        let bits_per_sample = self.bits_per_sample.unwrap();
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate.unwrap()
+    }
 
-        (sample_rate as usize * channels) / 1000
+    fn channels(&self) -> usize {
+        self.channels.unwrap().count()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
+    use std::time::Duration;
 
     use crate::BytesPerMillisecond;
     use hound::{SampleFormat, WavSpec};
@@ -69,6 +103,37 @@ mod tests {
         assert_eq!(wavspec.bytes_per_ms(), 88);
     }
 
+    #[test]
+    fn samples_in_is_exact() {
+        let wavspec = WavSpec {
+            sample_rate: 44100,
+            sample_format: SampleFormat::Int,
+            bits_per_sample: 16,
+            channels: 2,
+        };
+
+        assert_eq!(wavspec.samples_in(Duration::from_millis(1)), 88);
+        assert_eq!(wavspec.samples_in(Duration::from_millis(10)), 882);
+        assert_eq!(
+            wavspec.samples_in(Duration::from_secs(3600)),
+            44100 * 2 * 3600
+        );
+    }
+
+    #[test]
+    fn samples_in_works_below_1khz() {
+        let wavspec = WavSpec {
+            sample_rate: 500,
+            sample_format: SampleFormat::Int,
+            bits_per_sample: 16,
+            channels: 1,
+        };
+
+        assert_eq!(wavspec.bytes_per_ms(), 0);
+        assert_eq!(wavspec.samples_in(Duration::from_millis(50)), 25);
+        assert_eq!(wavspec.samples_in(Duration::from_secs(2)), 1000);
+    }
+
     #[test]
     fn codec_params_bytes_per_ms_works() {
         let bytes = std::fs::read("../test_files/test.wav").unwrap();
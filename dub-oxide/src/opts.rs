@@ -1,10 +1,88 @@
 use std::time::Duration;
 
-use crate::{BytesPerMillisecond, error::Error};
+use crate::{BytesPerMillisecond, error::Error, vad::VoiceActivityDetector};
+
+/// How the length of each chunk is decided.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SplitMode {
+    /// Cut every `n` samples (or earlier, at a pause).
+    Size(usize),
+    /// Cut into exactly `n` parts of roughly equal length, moving each cut to a nearby pause.
+    Parts(usize),
+    /// Cut at every pause lasting at least `n` samples, dropping the pauses themselves.
+    Pauses(usize),
+    /// Cut at each of the given sample positions.
+    Timestamps(Vec<usize>),
+}
+
+/// How the place to cut is picked when searching for a pause.
+///
+/// A chunk that starts inside a pause (the rest of the one the chunk before it was cut in) only
+/// ends in that same pause if there's no other one in range, and then it's cut where the pause
+/// ends rather than in its middle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CutPolicy {
+    /// Cut in the middle of the last run of silence in range. This is the default when splitting by
+    /// duration or size.
+    LastSilence,
+    /// Cut in the middle of the silent window closest to the target. This is the default when
+    /// splitting into parts or at timestamps.
+    ClosestToTarget,
+    /// Cut in the middle of the longest run of silence in range.
+    LongestSilence,
+    /// Cut in the middle of the quietest window in range, even if nothing is below the silence
+    /// threshold. This doesn't need a silence threshold.
+    QuietestWindow,
+}
+
+/// How the channels of multichannel audio are combined when looking for silence.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ChannelAnalysis {
+    /// Mix every channel down to mono first. This is the default.
+    #[default]
+    Mixdown,
+    /// Only count silence where every channel is silent, for example when each speaker in an
+    /// interview has their own channel.
+    AllSilent,
+    /// Count silence wherever at least one channel is silent.
+    AnySilent,
+    /// Only look at the channels with these indices, counting silence where all of them are
+    /// silent.
+    Channels(Vec<usize>),
+}
+
+/// What happens to chunks that contain nothing but silence.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SilentChunks {
+    /// Hand them out like any other chunk, without checking whether they're silent. This is the
+    /// default.
+    #[default]
+    Keep,
+    /// Hand them out, marked with [`AudioChunk::is_silent`](crate::AudioChunk::is_silent).
+    Flag,
+    /// Leave them out of the results altogether.
+    Drop,
+}
 
 pub struct SplitOpts {
-    frame_size: usize,
+    mode: SplitMode,
     silence_threshold: Option<f32>,
+    overlap: usize,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+    padding: usize,
+    snap_tolerance: Option<usize>,
+    /// The cut policy, if one was picked rather than left to the default for the mode.
+    cut_policy: Option<CutPolicy>,
+    silence_exit_threshold: Option<f32>,
+    min_silence_len: usize,
+    analysis_window: Option<usize>,
+    analysis_hop: Option<usize>,
+    noise_floor_margin: Option<f32>,
+    voice_activity: Option<VoiceActivityDetector>,
+    channel_analysis: ChannelAnalysis,
+    trim_silence: Option<usize>,
+    silent_chunks: SilentChunks,
 }
 
 impl SplitOpts {
@@ -19,8 +97,120 @@ impl SplitOpts {
         self.silence_threshold
     }
 
-    pub fn frame_size(&self) -> usize {
-        self.frame_size
+    /// The level (in dBFS) a pause has to rise above before it stops counting as silence. This is
+    /// the same as the silence threshold unless set separately.
+    pub fn silence_exit_threshold(&self) -> Option<f32> {
+        self.silence_exit_threshold.or(self.silence_threshold)
+    }
+
+    /// The shortest pause (in samples) that counts as silence.
+    pub fn min_silence_len(&self) -> usize {
+        self.min_silence_len
+    }
+
+    /// The length (in samples) of each window checked for silence, if set.
+    pub fn analysis_window(&self) -> Option<usize> {
+        self.analysis_window
+    }
+
+    /// The distance (in samples) between the start of one analysis window and the next, if set.
+    pub fn analysis_hop(&self) -> Option<usize> {
+        self.analysis_hop
+    }
+
+    /// How far (in dB) above the estimated noise floor the silence threshold is set, if the
+    /// threshold is picked automatically.
+    pub fn noise_floor_margin(&self) -> Option<f32> {
+        self.noise_floor_margin
+    }
+
+    /// The voice activity detector used to find pauses instead of a silence threshold, if set.
+    pub fn voice_activity(&self) -> Option<&VoiceActivityDetector> {
+        self.voice_activity.as_ref()
+    }
+
+    /// How channels are combined when looking for silence.
+    pub fn channel_analysis(&self) -> &ChannelAnalysis {
+        &self.channel_analysis
+    }
+
+    /// The most silence (in samples) left at the start and end of each chunk, if chunks are
+    /// trimmed.
+    pub fn trim_silence(&self) -> Option<usize> {
+        self.trim_silence
+    }
+
+    /// What happens to chunks that contain nothing but silence.
+    pub fn silent_chunks(&self) -> SilentChunks {
+        self.silent_chunks
+    }
+
+    pub fn mode(&self) -> &SplitMode {
+        &self.mode
+    }
+
+    /// How many samples each chunk shares with the end of the chunk before it.
+    pub fn overlap(&self) -> usize {
+        self.overlap
+    }
+
+    /// How many samples of silence are kept either side of each region when splitting on pauses.
+    pub fn padding(&self) -> usize {
+        self.padding
+    }
+
+    /// How far (in samples) a cut given with [`SplitOptsBuilder::split_at`] may be moved to land
+    /// on a pause.
+    pub fn snap_tolerance(&self) -> Option<usize> {
+        self.snap_tolerance
+    }
+
+    /// How the place to cut is picked when searching for a pause.
+    pub fn cut_policy(&self) -> CutPolicy {
+        self.cut_policy.unwrap_or(match self.mode {
+            SplitMode::Size(_) => CutPolicy::LastSilence,
+            _ => CutPolicy::ClosestToTarget,
+        })
+    }
+
+    /// The shortest chunk (in samples) a split may produce, other than the final remainder.
+    pub fn min_len(&self) -> Option<usize> {
+        self.min_len
+    }
+
+    /// The longest chunk (in samples) a split may produce, other than the final remainder.
+    pub fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    /// The first option that's been set which only means something when looking for silence,
+    /// other than the silence threshold itself, for splitters that can't look for it.
+    #[cfg(feature = "aiff")]
+    pub(crate) fn silence_option(&self) -> Option<&'static str> {
+        let options = [
+            ("cut_policy", self.cut_policy.is_some()),
+            (
+                "silence_exit_threshold",
+                self.silence_exit_threshold.is_some(),
+            ),
+            ("min_silence_duration", self.min_silence_len > 0),
+            ("analysis_window", self.analysis_window.is_some()),
+            ("analysis_hop", self.analysis_hop.is_some()),
+            ("auto_silence_threshold", self.noise_floor_margin.is_some()),
+            ("voice_activity", self.voice_activity.is_some()),
+            (
+                "channel_analysis",
+                self.channel_analysis != ChannelAnalysis::Mixdown,
+            ),
+            ("snap_to_silence", self.snap_tolerance.is_some()),
+            ("trim_silence", self.trim_silence.is_some()),
+            ("silent_chunks", self.silent_chunks != SilentChunks::Keep),
+        ];
+
+        options
+            .into_iter()
+            .find(|(_, is_set)| *is_set)
+            .map(|(option, _)| option)
     }
 }
 
@@ -28,8 +218,27 @@ pub struct SplitOptsBuilder<C> {
     codec: Option<C>,
     duration_chunk_criteria: Option<Duration>,
     memsize_chunk_criteria: Option<usize>,
+    parts_chunk_criteria: Option<usize>,
+    pause_chunk_criteria: Option<Duration>,
+    timestamp_chunk_criteria: Option<Vec<Duration>>,
     /// The silence threshold. If None, there will be no silence threshold.
     silence_threshold: Option<f32>,
+    overlap: Option<Duration>,
+    min_duration: Option<Duration>,
+    max_duration: Option<Duration>,
+    padding: Option<Duration>,
+    snap_tolerance: Option<Duration>,
+    cut_policy: Option<CutPolicy>,
+    silence_exit_threshold: Option<f32>,
+    min_silence_duration: Option<Duration>,
+    analysis_window: Option<Duration>,
+    analysis_hop: Option<Duration>,
+    auto_silence_threshold: bool,
+    noise_floor_margin: Option<f32>,
+    voice_activity: Option<VoiceActivityDetector>,
+    channel_analysis: Option<ChannelAnalysis>,
+    trim_silence: Option<Duration>,
+    silent_chunks: Option<SilentChunks>,
 }
 
 impl<C> SplitOptsBuilder<C>
@@ -58,38 +267,448 @@ where
         self
     }
 
+    /// Splits the audio into `parts` chunks of roughly equal length.
+    ///
+    /// If a silence threshold is set, each cut is moved to the pause closest to where it would
+    /// otherwise land. Audio with fewer frames than `parts` is split into one chunk per frame
+    /// instead, and empty audio into no chunks at all.
+    pub fn split_into_parts(mut self, parts: usize) -> Self {
+        self.parts_chunk_criteria = Some(parts);
+
+        self
+    }
+
+    /// Cuts the audio at every pause lasting at least `min_pause`, with no target chunk length.
+    ///
+    /// Only the non-silent regions between pauses are returned. Requires a silence threshold.
+    pub fn split_on_silence(mut self, min_pause: Duration) -> Self {
+        self.pause_chunk_criteria = Some(min_pause);
+
+        self
+    }
+
+    /// Keeps up to `padding` of silence either side of each region when splitting on silence.
+    pub fn silence_padding(mut self, padding: Duration) -> Self {
+        self.padding = Some(padding);
+
+        self
+    }
+
+    /// Cuts the audio at each of the given timestamps, measured from the start of the file.
+    pub fn split_at(mut self, timestamps: Vec<Duration>) -> Self {
+        self.timestamp_chunk_criteria = Some(timestamps);
+
+        self
+    }
+
+    /// Moves each cut given with [`SplitOptsBuilder::split_at`] to the nearest pause, as long as
+    /// it's no further than `tolerance` away. Requires a silence threshold.
+    pub fn snap_to_silence(mut self, tolerance: Duration) -> Self {
+        self.snap_tolerance = Some(tolerance);
+
+        self
+    }
+
+    /// Sets how the place to cut is picked when searching for a pause. See [`CutPolicy`] for the
+    /// defaults.
+    pub fn cut_policy(mut self, policy: CutPolicy) -> Self {
+        self.cut_policy = Some(policy);
+
+        self
+    }
+
     pub fn silence_threshold(mut self, threshold: f32) -> Self {
         self.silence_threshold = Some(threshold);
 
         self
     }
 
+    /// Picks the silence threshold from the audio itself, rather than using a fixed level.
+    ///
+    /// The level of every analysis window is measured first, and the quietest tenth are taken as
+    /// the noise floor. The threshold is set a margin above that (6dB unless changed with
+    /// [`SplitOptsBuilder::noise_floor_margin`]), but never more than halfway up to the level of
+    /// the louder parts. The chosen threshold is reported by
+    /// [`SplitResult::silence_threshold`](crate::result::SplitResult::silence_threshold).
+    pub fn auto_silence_threshold(mut self) -> Self {
+        self.auto_silence_threshold = true;
+
+        self
+    }
+
+    /// Sets how far (in dB) above the noise floor an automatic silence threshold is set.
+    pub fn noise_floor_margin(mut self, margin: f32) -> Self {
+        self.noise_floor_margin = Some(margin);
+
+        self
+    }
+
+    /// Treats anywhere `vad` finds no speech as a pause, instead of anywhere below a silence
+    /// threshold. Chunks then only end where speech actually stops, even over loud background
+    /// noise.
+    pub fn voice_activity(mut self, vad: VoiceActivityDetector) -> Self {
+        self.voice_activity = Some(vad);
+
+        self
+    }
+
+    /// Sets how the channels of multichannel audio are combined when looking for silence. Defaults
+    /// to [`ChannelAnalysis::Mixdown`].
+    pub fn channel_analysis(mut self, analysis: ChannelAnalysis) -> Self {
+        self.channel_analysis = Some(analysis);
+
+        self
+    }
+
+    /// Keeps treating a pause as silence until the level rises above `threshold` dBFS, rather than
+    /// as soon as it rises above the silence threshold. Must be at least the silence threshold.
+    pub fn silence_exit_threshold(mut self, threshold: f32) -> Self {
+        self.silence_exit_threshold = Some(threshold);
+
+        self
+    }
+
+    /// Only counts a pause as silence if it lasts at least `duration`, so brief gaps between
+    /// syllables aren't mistaken for somewhere to cut.
+    pub fn min_silence_duration(mut self, duration: Duration) -> Self {
+        self.min_silence_duration = Some(duration);
+
+        self
+    }
+
+    /// Sets the length of each window checked for silence. Defaults to 50ms.
+    ///
+    /// Shorter windows react faster, which suits speech, while longer windows are less likely to
+    /// mistake a quiet passage of music for a pause.
+    pub fn analysis_window(mut self, window: Duration) -> Self {
+        self.analysis_window = Some(window);
+
+        self
+    }
+
+    /// Sets how far apart consecutive analysis windows start. Defaults to the window length, so
+    /// windows don't overlap; a shorter hop gives overlapping, sliding windows.
+    pub fn analysis_hop(mut self, hop: Duration) -> Self {
+        self.analysis_hop = Some(hop);
+
+        self
+    }
+
+    /// Trims the silence at the start and end of each chunk down to at most `max_keep`, updating
+    /// the chunk's timestamps to match. The trimmed silence isn't part of any chunk. A chunk that's
+    /// silent throughout is trimmed down to its first `max_keep`. Requires a silence threshold.
+    pub fn trim_silence(mut self, max_keep: Duration) -> Self {
+        self.trim_silence = Some(max_keep);
+
+        self
+    }
+
+    /// Sets what happens to chunks that contain nothing but silence, judged with the same
+    /// threshold used to find pauses. Anything other than [`SilentChunks::Keep`] requires a
+    /// silence threshold.
+    pub fn silent_chunks(mut self, silent_chunks: SilentChunks) -> Self {
+        self.silent_chunks = Some(silent_chunks);
+
+        self
+    }
+
+    /// Makes every chunk after the first start `overlap` before the previous cut, so neighbouring
+    /// chunks share some audio.
+    pub fn overlap(mut self, overlap: Duration) -> Self {
+        self.overlap = Some(overlap);
+
+        self
+    }
+
+    /// Never cut a chunk shorter than `duration`. Pauses before this point are ignored.
+    ///
+    /// Can't be used with [`SplitOptsBuilder::split_into_parts`] or
+    /// [`SplitOptsBuilder::split_at`], which decide every cut themselves.
+    pub fn min_duration(mut self, duration: Duration) -> Self {
+        self.min_duration = Some(duration);
+
+        self
+    }
+
+    /// Never cut a chunk longer than `duration`. Pauses after this point are ignored.
+    ///
+    /// Can't be used with [`SplitOptsBuilder::split_into_parts`] or
+    /// [`SplitOptsBuilder::split_at`], which decide every cut themselves.
+    pub fn max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+
+        self
+    }
+
     pub fn build(self) -> Result<SplitOpts, Error> {
-        if self.duration_chunk_criteria.is_some() && self.memsize_chunk_criteria.is_some() {
-            return Err(Error::incompatible_options(
+        let criteria = [
+            (
                 "duration_chunk_criteria",
+                self.duration_chunk_criteria.is_some(),
+            ),
+            (
                 "memsize_chunk_criteria",
+                self.memsize_chunk_criteria.is_some(),
+            ),
+            ("parts_chunk_criteria", self.parts_chunk_criteria.is_some()),
+            ("pause_chunk_criteria", self.pause_chunk_criteria.is_some()),
+            (
+                "timestamp_chunk_criteria",
+                self.timestamp_chunk_criteria.is_some(),
+            ),
+        ];
+        let mut set = criteria.iter().filter(|(_, is_set)| *is_set);
+        if let (Some((first, _)), Some((second, _))) = (set.next(), set.next()) {
+            return Err(Error::incompatible_options(first, second));
+        }
+
+        let to_samples = |duration: Duration| {
+            let Some(codec) = &self.codec else {
+                return Err(Error::missing_builder_field("codec"));
+            };
+
+            Ok(codec.samples_in(duration))
+        };
+
+        if self.auto_silence_threshold && self.silence_threshold.is_some() {
+            return Err(Error::incompatible_options(
+                "silence_threshold",
+                "auto_silence_threshold",
             ));
         }
-        let frame_size = {
-            if let Some(duration) = self.duration_chunk_criteria {
-                let Some(codec) = self.codec else {
-                    todo!("write proper error");
-                };
+        if self.noise_floor_margin.is_some() && !self.auto_silence_threshold {
+            return Err(Error::missing_builder_field("auto_silence_threshold"));
+        }
+        if self.voice_activity.is_some() {
+            if self.silence_threshold.is_some() {
+                return Err(Error::incompatible_options(
+                    "voice_activity",
+                    "silence_threshold",
+                ));
+            }
+            if self.auto_silence_threshold {
+                return Err(Error::incompatible_options(
+                    "voice_activity",
+                    "auto_silence_threshold",
+                ));
+            }
+        }
+        let has_threshold = self.silence_threshold.is_some()
+            || self.auto_silence_threshold
+            || self.voice_activity.is_some();
 
-                (codec.bytes_per_ms() as u128 * duration.as_millis()) as usize
-            } else {
-                let Some(memsize) = self.memsize_chunk_criteria else {
-                    todo!("write proper error");
-                };
+        let mode = if let Some(duration) = self.duration_chunk_criteria {
+            SplitMode::Size(to_samples(duration)?)
+        } else if let Some(memsize) = self.memsize_chunk_criteria {
+            SplitMode::Size(memsize)
+        } else if let Some(parts) = self.parts_chunk_criteria {
+            if parts == 0 {
+                return Err(Error::invalid_option(
+                    "parts_chunk_criteria",
+                    "must be at least 1",
+                ));
+            }
+
+            SplitMode::Parts(parts)
+        } else if let Some(min_pause) = self.pause_chunk_criteria {
+            if !has_threshold {
+                return Err(Error::missing_builder_field("silence_threshold"));
+            }
+            if self.overlap.is_some() {
+                return Err(Error::incompatible_options(
+                    "pause_chunk_criteria",
+                    "overlap",
+                ));
+            }
+            if self.cut_policy.is_some() {
+                return Err(Error::incompatible_options(
+                    "pause_chunk_criteria",
+                    "cut_policy",
+                ));
+            }
+
+            SplitMode::Pauses(to_samples(min_pause)?)
+        } else if let Some(timestamps) = &self.timestamp_chunk_criteria {
+            let mut cuts = timestamps
+                .iter()
+                .map(|x| to_samples(*x))
+                .collect::<Result<Vec<_>, _>>()?;
+            cuts.sort_unstable();
+            cuts.dedup();
+
+            SplitMode::Timestamps(cuts)
+        } else {
+            return Err(Error::missing_builder_field(
+                "duration_chunk_criteria, memsize_chunk_criteria, parts_chunk_criteria, pause_chunk_criteria or timestamp_chunk_criteria",
+            ));
+        };
+
+        if self.snap_tolerance.is_some()
+            && !has_threshold
+            && self.cut_policy != Some(CutPolicy::QuietestWindow)
+        {
+            return Err(Error::missing_builder_field("silence_threshold"));
+        }
 
-                memsize
+        if let Some(exit_threshold) = self.silence_exit_threshold {
+            if self.auto_silence_threshold {
+                return Err(Error::incompatible_options(
+                    "auto_silence_threshold",
+                    "silence_exit_threshold",
+                ));
             }
+            let Some(threshold) = self.silence_threshold else {
+                return Err(Error::missing_builder_field("silence_threshold"));
+            };
+            if exit_threshold < threshold {
+                return Err(Error::invalid_option(
+                    "silence_exit_threshold",
+                    "must not be below silence_threshold",
+                ));
+            }
+        }
+
+        let overlap = self
+            .overlap
+            .map(to_samples)
+            .transpose()?
+            .unwrap_or_default();
+        if let SplitMode::Size(size) = mode
+            && overlap >= size
+        {
+            return Err(Error::invalid_option(
+                "overlap",
+                "must be shorter than the chunk length",
+            ));
+        }
+
+        // Parts and timestamps decide every cut themselves, so there's nothing to bound.
+        let fixed_cuts = match mode {
+            SplitMode::Parts(_) => Some("parts_chunk_criteria"),
+            SplitMode::Timestamps(_) => Some("timestamp_chunk_criteria"),
+            _ => None,
         };
+        if let Some(criteria) = fixed_cuts {
+            if self.min_duration.is_some() {
+                return Err(Error::incompatible_options(criteria, "min_duration"));
+            }
+            if self.max_duration.is_some() {
+                return Err(Error::incompatible_options(criteria, "max_duration"));
+            }
+        }
+
+        let min_len = self.min_duration.map(to_samples).transpose()?;
+        let max_len = self.max_duration.map(to_samples).transpose()?;
+        if let (Some(min_len), Some(max_len)) = (min_len, max_len)
+            && min_len > max_len
+        {
+            return Err(Error::invalid_option(
+                "min_duration",
+                "must not be longer than max_duration",
+            ));
+        }
+        if max_len == Some(0) {
+            return Err(Error::invalid_option(
+                "max_duration",
+                "must be at least one frame long",
+            ));
+        }
+        if let Some(max_len) = max_len
+            && max_len <= overlap
+        {
+            return Err(Error::invalid_option(
+                "max_duration",
+                "must be longer than the overlap",
+            ));
+        }
+
+        let padding = self
+            .padding
+            .map(to_samples)
+            .transpose()?
+            .unwrap_or_default();
+        let snap_tolerance = self.snap_tolerance.map(to_samples).transpose()?;
+        let min_silence_len = self
+            .min_silence_duration
+            .map(to_samples)
+            .transpose()?
+            .unwrap_or_default();
+        let analysis_window = self.analysis_window.map(to_samples).transpose()?;
+        let analysis_hop = self.analysis_hop.map(to_samples).transpose()?;
+        if analysis_window == Some(0) {
+            return Err(Error::invalid_option(
+                "analysis_window",
+                "must be at least one sample long",
+            ));
+        }
+        match (analysis_hop, analysis_window) {
+            (Some(0), _) => {
+                return Err(Error::invalid_option(
+                    "analysis_hop",
+                    "must be at least one sample long",
+                ));
+            }
+            (Some(hop), Some(window)) if hop > window => {
+                return Err(Error::invalid_option(
+                    "analysis_hop",
+                    "must not be longer than analysis_window",
+                ));
+            }
+            _ => {}
+        }
+
+        if let Some(ChannelAnalysis::Channels(selected)) = &self.channel_analysis {
+            if selected.is_empty() {
+                return Err(Error::invalid_option(
+                    "channel_analysis",
+                    "must select at least one channel",
+                ));
+            }
+            if let Some(codec) = &self.codec
+                && selected.iter().any(|index| *index >= codec.channels())
+            {
+                return Err(Error::invalid_option(
+                    "channel_analysis",
+                    "selects a channel the audio doesn't have",
+                ));
+            }
+        }
+
+        if self.trim_silence.is_some() {
+            if !has_threshold {
+                return Err(Error::missing_builder_field("silence_threshold"));
+            }
+            if self.overlap.is_some() {
+                return Err(Error::incompatible_options("trim_silence", "overlap"));
+            }
+        }
+        let trim_silence = self.trim_silence.map(to_samples).transpose()?;
+
+        let silent_chunks = self.silent_chunks.unwrap_or_default();
+        if silent_chunks != SilentChunks::Keep && !has_threshold {
+            return Err(Error::missing_builder_field("silence_threshold"));
+        }
 
         Ok(SplitOpts {
-            frame_size,
+            mode,
             silence_threshold: self.silence_threshold,
+            overlap,
+            min_len,
+            max_len,
+            padding,
+            snap_tolerance,
+            cut_policy: self.cut_policy,
+            silence_exit_threshold: self.silence_exit_threshold,
+            min_silence_len,
+            analysis_window,
+            analysis_hop,
+            noise_floor_margin: self
+                .auto_silence_threshold
+                .then(|| self.noise_floor_margin.unwrap_or(6.0)),
+            voice_activity: self.voice_activity,
+            channel_analysis: self.channel_analysis.unwrap_or_default(),
+            trim_silence,
+            silent_chunks,
         })
     }
 }
@@ -100,7 +719,26 @@ impl<C> Default for SplitOptsBuilder<C> {
             codec: None,
             duration_chunk_criteria: None,
             memsize_chunk_criteria: None,
+            parts_chunk_criteria: None,
+            pause_chunk_criteria: None,
+            timestamp_chunk_criteria: None,
             silence_threshold: None,
+            overlap: None,
+            min_duration: None,
+            max_duration: None,
+            padding: None,
+            snap_tolerance: None,
+            cut_policy: None,
+            silence_exit_threshold: None,
+            min_silence_duration: None,
+            analysis_window: None,
+            analysis_hop: None,
+            auto_silence_threshold: false,
+            noise_floor_margin: None,
+            voice_activity: None,
+            channel_analysis: None,
+            trim_silence: None,
+            silent_chunks: None,
         }
     }
 }
@@ -0,0 +1,222 @@
+use std::{
+    ops::{Deref, Range},
+    slice::Iter,
+    time::Duration,
+    vec::IntoIter,
+};
+
+use crate::{
+    AudioChunk,
+    formats::common::frames_to_duration,
+    result::{CutReason, Samples},
+};
+
+/// Where a single chunk would be cut, without any of its audio.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlannedChunk {
+    sample_rate: u32,
+    /// The frame this chunk starts at, counting from the start of the file.
+    start_frame: usize,
+    /// The frame just after the end of this chunk.
+    end_frame: usize,
+    /// The frames of this chunk that aren't shared with its neighbours.
+    core: Range<usize>,
+    cut_reason: CutReason,
+    cut_level: Option<f32>,
+    silent: bool,
+}
+
+impl PlannedChunk {
+    /// Plans a chunk covering `start_frame..end_frame` of audio sampled at `sample_rate`.
+    pub fn new(sample_rate: u32, start_frame: usize, end_frame: usize) -> Self {
+        let end_frame = end_frame.max(start_frame);
+
+        Self {
+            sample_rate,
+            start_frame,
+            end_frame,
+            core: start_frame..end_frame,
+            cut_reason: CutReason::Target,
+            cut_level: None,
+            silent: false,
+        }
+    }
+
+    /// Sets the frames of this chunk that don't overlap with the previous chunk.
+    pub fn with_core_range(mut self, core_start: usize, core_end: usize) -> Self {
+        self.core = core_start..core_end;
+
+        self
+    }
+
+    /// Records what decided where this chunk ends.
+    pub fn with_cut_reason(mut self, reason: CutReason) -> Self {
+        self.cut_reason = reason;
+
+        self
+    }
+
+    /// Records the level (in dBFS) of the audio around the cut at the end of this chunk.
+    pub fn with_cut_level(mut self, level: Option<f32>) -> Self {
+        self.cut_level = level;
+
+        self
+    }
+
+    /// Marks this chunk as containing nothing but silence.
+    pub fn with_silent(mut self, silent: bool) -> Self {
+        self.silent = silent;
+
+        self
+    }
+
+    /// The first frame of this chunk, counting from the start of the file.
+    pub fn start_frame(&self) -> usize {
+        self.start_frame
+    }
+
+    /// The frame just after the end of this chunk.
+    pub fn end_frame(&self) -> usize {
+        self.end_frame
+    }
+
+    pub fn frames(&self) -> usize {
+        self.end_frame - self.start_frame
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// When this chunk starts, measured from the start of the file.
+    pub fn start(&self) -> Duration {
+        frames_to_duration(self.start_frame, self.sample_rate)
+    }
+
+    /// When this chunk ends, measured from the start of the file.
+    pub fn end(&self) -> Duration {
+        frames_to_duration(self.end_frame, self.sample_rate)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.end() - self.start()
+    }
+
+    /// The frames of this chunk that aren't shared with the previous chunk.
+    pub fn core_range(&self) -> Range<usize> {
+        self.core.clone()
+    }
+
+    /// What decided where this chunk ends.
+    pub fn cut_reason(&self) -> CutReason {
+        self.cut_reason
+    }
+
+    /// The level (in dBFS) of the audio around the cut at the end of this chunk. The lower it is,
+    /// the cleaner the cut.
+    pub fn cut_level(&self) -> Option<f32> {
+        self.cut_level
+    }
+
+    /// Whether this chunk contains nothing but silence, if silent chunks were being flagged.
+    pub fn is_silent(&self) -> bool {
+        self.silent
+    }
+
+    /// Builds the chunk this plan describes as a view of `range` of `buffer`, which holds its
+    /// samples. The chunk's levels are left for the caller to fill in.
+    pub(crate) fn to_chunk<T>(
+        &self,
+        buffer: impl Into<Samples<T>>,
+        range: Range<usize>,
+        frame_width: usize,
+        index: usize,
+    ) -> AudioChunk<T>
+    where
+        T: Clone,
+    {
+        AudioChunk::from_samples(
+            buffer.into(),
+            range,
+            frame_width,
+            self.sample_rate,
+            self.start_frame,
+        )
+        .with_core_range(self.core.start, self.core.end)
+        .with_cut_reason(self.cut_reason)
+        .with_cut_level(self.cut_level)
+        .with_silent(self.silent)
+        .with_index(index)
+    }
+}
+
+/// The cuts a split would make, worked out without copying any audio.
+///
+/// Plans come from [`AudioSplitter::plan`](crate::AudioSplitter::plan), and can be adjusted
+/// before being handed to [`AudioSplitter::apply`](crate::AudioSplitter::apply) to extract the
+/// audio.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SplitPlan {
+    chunks: Vec<PlannedChunk>,
+    silence_threshold: Option<f32>,
+}
+
+impl SplitPlan {
+    pub fn new(chunks: Vec<PlannedChunk>) -> Self {
+        Self {
+            chunks,
+            silence_threshold: None,
+        }
+    }
+
+    /// Records the silence threshold (in dBFS) the plan was made with.
+    pub fn with_silence_threshold(mut self, threshold: Option<f32>) -> Self {
+        self.silence_threshold = threshold;
+
+        self
+    }
+
+    /// The silence threshold (in dBFS) the plan was made with, including one picked
+    /// automatically.
+    pub fn silence_threshold(&self) -> Option<f32> {
+        self.silence_threshold
+    }
+
+    pub fn chunks(&self) -> &[PlannedChunk] {
+        &self.chunks
+    }
+
+    /// The planned chunks, for adding, removing or moving cuts before the plan is applied.
+    pub fn chunks_mut(&mut self) -> &mut Vec<PlannedChunk> {
+        &mut self.chunks
+    }
+
+    pub fn iter(&self) -> Iter<'_, PlannedChunk> {
+        self.chunks.iter()
+    }
+
+    /// The timestamp of every cut between two chunks.
+    pub fn cuts(&self) -> Vec<Duration> {
+        self.chunks
+            .split_last()
+            .map(|(_, rest)| rest.iter().map(|x| x.end()).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl IntoIterator for SplitPlan {
+    type Item = PlannedChunk;
+    type IntoIter = IntoIter<PlannedChunk>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.into_iter()
+    }
+}
+
+impl Deref for SplitPlan {
+    type Target = [PlannedChunk];
+
+    fn deref(&self) -> &Self::Target {
+        &self.chunks
+    }
+}
@@ -1,41 +1,353 @@
-use std::{ops::Deref, slice::Iter, vec::IntoIter};
+use std::{
+    ops::{Deref, Range},
+    slice::Iter,
+    sync::Arc,
+    time::Duration,
+    vec::IntoIter,
+};
 
+#[cfg(feature = "mmap")]
+use crate::formats::mapped::MappedSamples;
+use crate::{
+    formats::common::{dbfs, frames_to_duration},
+    opts::CutPolicy,
+    plan::PlannedChunk,
+};
+
+/// What decided where a chunk ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CutReason {
+    /// The cut was placed by searching for a pause with the given policy.
+    Policy(CutPolicy),
+    /// The chunk ends at a pause long enough to split on.
+    Pause,
+    /// No pause was searched for or found, so the chunk was cut at its target length (or the
+    /// longest length allowed).
+    Target,
+    /// The chunk runs to the end of the audio.
+    EndOfInput,
+}
+
+/// The samples a chunk is a view into.
+#[derive(Clone)]
+pub(crate) enum Samples<T> {
+    Shared(Arc<[T]>),
+    /// Samples used where they sit in a memory-mapped file.
+    #[cfg(feature = "mmap")]
+    Mapped(MappedSamples<T>),
+}
+
+impl<T> From<Arc<[T]>> for Samples<T> {
+    fn from(buffer: Arc<[T]>) -> Self {
+        Self::Shared(buffer)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<T> From<MappedSamples<T>> for Samples<T> {
+    fn from(samples: MappedSamples<T>) -> Self {
+        Self::Mapped(samples)
+    }
+}
+
+impl<T> Deref for Samples<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Shared(buffer) => buffer,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(samples) => samples,
+        }
+    }
+}
+
+/// A chunk of audio, along with where it came from and what decided its length.
+///
+/// A chunk is a view into a buffer of samples that may be shared with other chunks from the same
+/// split (or, for a memory-mapped file, into the file itself), so splitting doesn't hold a second
+/// copy of the audio. Cloning a chunk is cheap for the
+/// same reason; use [`AudioChunk::to_owned`] for a chunk with its own copy of its samples.
+#[derive(Clone)]
 pub struct AudioChunk<T> {
-    bytes: Vec<T>,
+    /// The samples this chunk is a view into.
+    buffer: Samples<T>,
+    /// The part of `buffer` this chunk covers.
+    range: Range<usize>,
     idx: usize,
-    timestamp_start: usize,
-    timestamp_end: usize,
+    /// How many values of `T` make up a single frame (one value per channel for PCM samples).
+    frame_width: usize,
+    sample_rate: u32,
+    /// The frame this chunk starts at, counting from the start of the file.
+    start_frame: usize,
+    /// The frame just after the end of this chunk.
+    end_frame: usize,
+    /// The frames of this chunk that aren't shared with its neighbours.
+    core: Range<usize>,
+    cut_reason: CutReason,
+    silent: bool,
+    /// Where this chunk comes in the results, counting from 0.
+    index: usize,
+    /// The peak and RMS levels of this chunk, relative to full scale.
+    peak: f32,
+    rms: f32,
+    /// The level (in dBFS) of the audio around the cut at the end of this chunk.
+    cut_level: Option<f32>,
 }
 
 impl<T> AudioChunk<T>
 where
     T: Clone,
 {
-    pub fn new(data: &[T], timestamp_start: usize, timestamp_end: usize) -> Self {
+    /// Creates a chunk holding a copy of `data`, which starts `start_frame` frames into audio
+    /// sampled at `sample_rate`.
+    pub fn new(data: &[T], frame_width: usize, sample_rate: u32, start_frame: usize) -> Self {
+        Self::from_shared(
+            Arc::from(data),
+            0..data.len(),
+            frame_width,
+            sample_rate,
+            start_frame,
+        )
+    }
+
+    /// Creates a chunk that is a view of `range` of `buffer`, without copying it. The view starts
+    /// `start_frame` frames into audio sampled at `sample_rate`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds for `buffer`.
+    pub fn from_shared(
+        buffer: Arc<[T]>,
+        range: Range<usize>,
+        frame_width: usize,
+        sample_rate: u32,
+        start_frame: usize,
+    ) -> Self {
+        Self::from_samples(buffer.into(), range, frame_width, sample_rate, start_frame)
+    }
+
+    pub(crate) fn from_samples(
+        buffer: Samples<T>,
+        range: Range<usize>,
+        frame_width: usize,
+        sample_rate: u32,
+        start_frame: usize,
+    ) -> Self {
+        assert!(
+            range.start <= range.end && range.end <= buffer.len(),
+            "chunk range {range:?} is out of bounds for a buffer of {} samples",
+            buffer.len()
+        );
+
+        let frame_width = frame_width.max(1);
+        let end_frame = start_frame + range.len() / frame_width;
+
         Self {
-            bytes: data.to_vec(),
+            buffer,
+            range,
             idx: 0,
-            timestamp_start,
-            timestamp_end,
+            frame_width,
+            sample_rate,
+            start_frame,
+            end_frame,
+            core: start_frame..end_frame,
+            cut_reason: CutReason::Target,
+            silent: false,
+            index: 0,
+            peak: 0.0,
+            rms: 0.0,
+            cut_level: None,
         }
     }
 
+    /// Sets the frames of this chunk that don't overlap with the previous chunk.
+    pub fn with_core_range(mut self, core_start: usize, core_end: usize) -> Self {
+        self.core = core_start..core_end;
+
+        self
+    }
+
+    /// Records what decided where this chunk ends.
+    pub fn with_cut_reason(mut self, reason: CutReason) -> Self {
+        self.cut_reason = reason;
+
+        self
+    }
+
+    /// Sets where this chunk comes in the results.
+    pub fn with_index(mut self, index: usize) -> Self {
+        self.index = index;
+
+        self
+    }
+
+    /// Records the peak and RMS levels of this chunk, relative to full scale.
+    pub fn with_levels(mut self, peak: f32, rms: f32) -> Self {
+        self.peak = peak;
+        self.rms = rms;
+
+        self
+    }
+
+    /// Records the level (in dBFS) of the audio around the cut at the end of this chunk.
+    pub fn with_cut_level(mut self, level: Option<f32>) -> Self {
+        self.cut_level = level;
+
+        self
+    }
+
+    /// Where this chunk comes in the results, counting from 0.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The largest absolute sample value in this chunk, relative to full scale (so between 0 and
+    /// 1).
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    /// The peak level of this chunk in dBFS.
+    pub fn peak_dbfs(&self) -> f32 {
+        dbfs(self.peak)
+    }
+
+    /// The RMS level of this chunk, relative to full scale.
+    pub fn rms(&self) -> f32 {
+        self.rms
+    }
+
+    /// The RMS level of this chunk in dBFS.
+    pub fn dbfs(&self) -> f32 {
+        dbfs(self.rms)
+    }
+
+    /// The level (in dBFS) of the audio around the cut at the end of this chunk, measured over the
+    /// same window used to look for silence. This is `None` for the last chunk and for formats
+    /// that don't look for silence.
+    pub fn cut_level(&self) -> Option<f32> {
+        self.cut_level
+    }
+
+    /// Whether this chunk was cut at a pause found by silence detection, rather than at a hard
+    /// limit or the end of the audio.
+    pub fn is_cut_at_silence(&self) -> bool {
+        matches!(self.cut_reason, CutReason::Policy(_) | CutReason::Pause)
+    }
+
+    /// Marks this chunk as containing nothing but silence.
+    pub fn with_silent(mut self, silent: bool) -> Self {
+        self.silent = silent;
+
+        self
+    }
+
+    /// Whether this chunk contains nothing but silence. This is only ever set when splitting with
+    /// [`SilentChunks::Flag`](crate::opts::SilentChunks::Flag).
+    pub fn is_silent(&self) -> bool {
+        self.silent
+    }
+
+    /// What decided where this chunk ends.
+    pub fn cut_reason(&self) -> CutReason {
+        self.cut_reason
+    }
+
+    /// The first frame of this chunk, counting from the start of the file.
+    pub fn start_frame(&self) -> usize {
+        self.start_frame
+    }
+
+    /// The frame just after the end of this chunk.
+    pub fn end_frame(&self) -> usize {
+        self.end_frame
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// When this chunk starts, measured from the start of the file.
+    pub fn start(&self) -> Duration {
+        frames_to_duration(self.start_frame, self.sample_rate)
+    }
+
+    /// When this chunk ends, measured from the start of the file.
+    pub fn end(&self) -> Duration {
+        frames_to_duration(self.end_frame, self.sample_rate)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.end() - self.start()
+    }
+
+    /// When this chunk starts, in whole milliseconds.
     pub fn timestamp_start(&self) -> usize {
-        self.timestamp_start
+        self.start().as_millis() as usize
     }
 
+    /// When this chunk ends, in whole milliseconds.
     pub fn timestamp_end(&self) -> usize {
-        self.timestamp_end
+        self.end().as_millis() as usize
+    }
+
+    /// The first frame of the part of this chunk that isn't shared with the previous chunk.
+    pub fn core_start_frame(&self) -> usize {
+        self.core.start
+    }
+
+    pub fn core_end_frame(&self) -> usize {
+        self.core.end
+    }
+
+    /// When the part of this chunk that isn't shared with the previous chunk starts.
+    pub fn core_start(&self) -> Duration {
+        frames_to_duration(self.core.start, self.sample_rate)
+    }
+
+    pub fn core_end(&self) -> Duration {
+        frames_to_duration(self.core.end, self.sample_rate)
+    }
+
+    /// The start of the part of this chunk that isn't shared with the previous chunk, in whole
+    /// milliseconds.
+    pub fn core_timestamp_start(&self) -> usize {
+        self.core_start().as_millis() as usize
+    }
+
+    pub fn core_timestamp_end(&self) -> usize {
+        self.core_end().as_millis() as usize
+    }
+
+    /// A copy of this chunk with its own buffer, holding only its samples, so it no longer keeps
+    /// the rest of a shared buffer (or a memory-mapped file) alive.
+    pub fn to_owned(&self) -> Self {
+        Self {
+            buffer: Samples::Shared(Arc::from(&self[..])),
+            range: 0..self.range.len(),
+            ..self.clone()
+        }
+    }
+
+    /// Where this chunk was cut, without its audio.
+    pub fn to_planned(&self) -> PlannedChunk {
+        PlannedChunk::new(self.sample_rate, self.start_frame, self.end_frame)
+            .with_core_range(self.core.start, self.core.end)
+            .with_cut_reason(self.cut_reason)
+            .with_cut_level(self.cut_level)
+            .with_silent(self.silent)
+    }
+
+    /// The number of whole frames in this chunk.
+    pub fn frames(&self) -> usize {
+        self.range.len() / self.frame_width
     }
 }
 
 impl AudioChunk<i16> {
     pub fn to_bytes_vec(&self) -> Vec<u8> {
-        self.bytes
-            .clone()
-            .into_iter()
-            .flat_map(|x| x.to_le_bytes())
-            .collect()
+        self.iter().flat_map(|x| x.to_le_bytes()).collect()
     }
 }
 
@@ -43,16 +355,19 @@ impl<T> Deref for AudioChunk<T> {
     type Target = [T];
 
     fn deref(&self) -> &Self::Target {
-        &self.bytes.as_slice()
+        &self.buffer[self.range.clone()]
     }
 }
 
-impl Iterator for AudioChunk<i16> {
-    type Item = i16;
+impl<T> Iterator for AudioChunk<T>
+where
+    T: Copy,
+{
+    type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.idx < self.bytes.len() {
-            let res = self.bytes[self.idx];
+        if self.idx < self.range.len() {
+            let res = self.buffer[self.range.start + self.idx];
             self.idx += 1;
             Some(res) // Consumes elements
         } else {
@@ -64,6 +379,7 @@ impl Iterator for AudioChunk<i16> {
 pub struct SplitResult<T, C> {
     chunks: Vec<AudioChunk<T>>,
     codec_params: C,
+    silence_threshold: Option<f32>,
 }
 
 impl<T, C> SplitResult<T, C> {
@@ -71,9 +387,23 @@ impl<T, C> SplitResult<T, C> {
         Self {
             chunks,
             codec_params,
+            silence_threshold: None,
         }
     }
 
+    /// Records the silence threshold (in dBFS) the audio was split with.
+    pub fn with_silence_threshold(mut self, threshold: Option<f32>) -> Self {
+        self.silence_threshold = threshold;
+
+        self
+    }
+
+    /// The silence threshold (in dBFS) the audio was split with, including one picked
+    /// automatically.
+    pub fn silence_threshold(&self) -> Option<f32> {
+        self.silence_threshold
+    }
+
     pub fn chunks(&self) -> &[AudioChunk<T>] {
         &self.chunks
     }
@@ -82,12 +412,19 @@ impl<T, C> SplitResult<T, C> {
         self.chunks
     }
 
-    pub fn iter(&self) -> Iter<AudioChunk<T>> {
+    pub fn iter(&self) -> Iter<'_, AudioChunk<T>> {
         self.chunks.iter()
     }
 
-    pub fn into_iter(self) -> IntoIter<AudioChunk<T>> {
-        self.chunks.into_iter()
+    /// The non-overlapping frames of each chunk.
+    ///
+    /// When splitting by duration, size, parts or timestamps, these ranges line up end to end and
+    /// cover the input exactly once, even when chunks overlap. That no longer holds once audio is
+    /// left out: [`split_on_silence`](crate::opts::SplitOptsBuilder::split_on_silence),
+    /// [`trim_silence`](crate::opts::SplitOptsBuilder::trim_silence) and
+    /// [`SilentChunks::Drop`](crate::opts::SilentChunks::Drop) all leave gaps between them.
+    pub fn core_ranges(&self) -> Vec<Range<usize>> {
+        self.chunks.iter().map(|x| x.core.clone()).collect()
     }
 
     pub fn get_codec(&self) -> &C {
@@ -111,6 +448,15 @@ impl<C> SplitResult<i16, C> {
     }
 }
 
+impl<T, C> IntoIterator for SplitResult<T, C> {
+    type Item = AudioChunk<T>;
+    type IntoIter = IntoIter<AudioChunk<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.into_iter()
+    }
+}
+
 impl<T, C> Deref for SplitResult<T, C> {
     type Target = [AudioChunk<T>];
 
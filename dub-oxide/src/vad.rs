@@ -0,0 +1,235 @@
+//! A simple voice activity detector.
+//!
+//! Level alone can't tell quiet speech apart from loud background noise, so alongside the energy
+//! of each window this also looks at how often the signal crosses zero and how flat its spectrum
+//! is. Noise crosses zero far more often than voiced speech and has a much flatter spectrum.
+
+use std::f32::consts::PI;
+
+use num::NumCast;
+
+use crate::formats::common::{dbfs, mixdown, rms};
+
+/// The most samples looked at when measuring spectral flatness.
+const MAX_FFT_LEN: usize = 1024;
+
+/// Decides whether a window of audio contains speech.
+///
+/// A window counts as speech when it's loud enough, crosses zero rarely enough and has a spectrum
+/// that's peaky enough. The defaults suit speech over steady background noise such as hiss or hum
+/// from air conditioning.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VoiceActivityDetector {
+    energy_threshold: f32,
+    max_zero_crossing_rate: f32,
+    max_spectral_flatness: f32,
+}
+
+impl VoiceActivityDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Windows quieter than `threshold` dBFS are never speech. Defaults to -50dBFS.
+    pub fn energy_threshold(mut self, threshold: f32) -> Self {
+        self.energy_threshold = threshold;
+
+        self
+    }
+
+    /// Windows that cross zero on more than `rate` of their samples (between 0 and 1) are never
+    /// speech. Defaults to 0.35.
+    pub fn max_zero_crossing_rate(mut self, rate: f32) -> Self {
+        self.max_zero_crossing_rate = rate;
+
+        self
+    }
+
+    /// Windows with a spectral flatness above `flatness` (between 0 for a pure tone and 1 for
+    /// white noise) are never speech. Defaults to 0.4.
+    pub fn max_spectral_flatness(mut self, flatness: f32) -> Self {
+        self.max_spectral_flatness = flatness;
+
+        self
+    }
+
+    /// Whether the interleaved `samples`, with `channels` channels and a full-scale sample value
+    /// of `full_scale`, contain speech.
+    pub fn is_speech<T>(&self, samples: &[T], channels: usize, full_scale: f32) -> bool
+    where
+        T: NumCast + Copy,
+    {
+        let mono = mixdown(samples, channels, full_scale);
+        if mono.is_empty() {
+            return false;
+        }
+
+        dbfs(rms(&mono, 1.0)) >= self.energy_threshold
+            && zero_crossing_rate(&mono) <= self.max_zero_crossing_rate
+            && spectral_flatness(&mono) <= self.max_spectral_flatness
+    }
+}
+
+impl Default for VoiceActivityDetector {
+    fn default() -> Self {
+        Self {
+            energy_threshold: -50.0,
+            max_zero_crossing_rate: 0.35,
+            max_spectral_flatness: 0.4,
+        }
+    }
+}
+
+/// The fraction of neighbouring samples that have opposite signs.
+pub fn zero_crossing_rate(samples: &[f32]) -> f32 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+
+    let crossings = samples
+        .windows(2)
+        .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+        .count();
+
+    crossings as f32 / (samples.len() - 1) as f32
+}
+
+/// The geometric mean of the power spectrum divided by its arithmetic mean. This is close to 1
+/// for noise and close to 0 for tonal sounds like voiced speech.
+///
+/// Only the middle of long inputs (up to 1024 samples) is looked at.
+pub fn spectral_flatness(samples: &[f32]) -> f32 {
+    let len = samples.len().min(MAX_FFT_LEN);
+    if len < 4 {
+        return 1.0;
+    }
+
+    let len = 1 << len.ilog2();
+    let start = (samples.len() - len) / 2;
+
+    // A Hann window stops the edges of the window showing up as broadband noise.
+    let mut re: Vec<f32> = samples[start..start + len]
+        .iter()
+        .enumerate()
+        .map(|(i, x)| x * (0.5 - 0.5 * (2.0 * PI * i as f32 / len as f32).cos()))
+        .collect();
+    let mut im = vec![0.0; len];
+    fft(&mut re, &mut im);
+
+    // Skip the DC bin, which only says how far the signal is offset from zero.
+    let power: Vec<f32> = (1..len / 2)
+        .map(|i| re[i] * re[i] + im[i] * im[i] + f32::EPSILON)
+        .collect();
+    let arithmetic_mean = power.iter().sum::<f32>() / power.len() as f32;
+    let geometric_mean = (power.iter().map(|x| x.ln()).sum::<f32>() / power.len() as f32).exp();
+
+    (geometric_mean / arithmetic_mean).clamp(0.0, 1.0)
+}
+
+/// An in-place radix-2 fast Fourier transform. `re.len()` must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+
+    use super::*;
+
+    fn noise(len: usize, amplitude: f32) -> Vec<f32> {
+        let mut state = 0x2545_f491_u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state as f32 / u32::MAX as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    /// A 150Hz buzz with a few harmonics, a rough stand-in for a voiced vowel.
+    fn voice(len: usize, sample_rate: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / sample_rate;
+                (1..=4)
+                    .map(|h| (t * 150.0 * h as f32 * TAU).sin() * 0.3 / h as f32)
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fft_finds_a_single_tone() {
+        let mut re: Vec<f32> = (0..64)
+            .map(|i| (i as f32 * TAU * 4.0 / 64.0).cos())
+            .collect();
+        let mut im = vec![0.0; 64];
+        fft(&mut re, &mut im);
+
+        let peak = (0..32)
+            .max_by(|a, b| re[*a].hypot(im[*a]).total_cmp(&re[*b].hypot(im[*b])))
+            .unwrap();
+        assert_eq!(peak, 4);
+    }
+
+    #[test]
+    fn noise_is_flat_and_a_tone_is_not() {
+        assert!(spectral_flatness(&noise(512, 0.5)) > 0.4);
+        assert!(spectral_flatness(&voice(512, 8000.0)) < 0.1);
+    }
+
+    #[test]
+    fn zero_crossing_rate_matches_frequency() {
+        let tone: Vec<f32> = (0..8000)
+            .map(|i| (i as f32 / 8000.0 * 440.0 * TAU + 0.1).sin())
+            .collect();
+
+        assert!((zero_crossing_rate(&tone) - 0.11).abs() < 0.01);
+    }
+
+    #[test]
+    fn voice_is_speech_but_loud_noise_is_not() {
+        let vad = VoiceActivityDetector::new();
+
+        assert!(vad.is_speech(&voice(400, 8000.0), 1, 1.0));
+        assert!(!vad.is_speech(&noise(400, 0.5), 1, 1.0));
+        assert!(!vad.is_speech(&[0.0f32; 400], 1, 1.0));
+    }
+}
@@ -1,13 +1,69 @@
-use std::time::Duration;
+use std::{io::Cursor, time::Duration};
 use tracing_subscriber::filter::LevelFilter;
 
-use dub_oxide::{AudioSplitter, WavSplitter, opts::SplitOpts};
+use dub_oxide::{
+    AudioSplitter, WavSplitter,
+    formats::wav::WavSample,
+    opts::{ChannelAnalysis, CutPolicy, SilentChunks, SplitOpts, SplitOptsBuilder},
+    plan::{PlannedChunk, SplitPlan},
+    result::CutReason,
+    vad::VoiceActivityDetector,
+};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+
+/// Builds an in-memory WAV file `seconds` seconds long, where `signal` gives the value of every
+/// channel at each point in time (in seconds) as a fraction of full scale.
+fn wav_from_fn(spec: WavSpec, seconds: u32, signal: impl Fn(f32) -> f32) -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    let mut writer = WavWriter::new(&mut bytes, spec).unwrap();
+
+    let frames = spec.sample_rate * seconds;
+    for frame in 0..frames {
+        let sample = signal(frame as f32 / spec.sample_rate as f32);
+
+        for _ in 0..spec.channels {
+            match (spec.sample_format, spec.bits_per_sample) {
+                (SampleFormat::Float, _) => writer.write_sample(sample),
+                (SampleFormat::Int, 8) => writer.write_sample((sample * i8::MAX as f32) as i8),
+                (SampleFormat::Int, 16) => writer.write_sample((sample * i16::MAX as f32) as i16),
+                (SampleFormat::Int, bits) => {
+                    let full_scale = ((1i64 << (bits - 1)) - 1) as f32;
+                    writer.write_sample((sample * full_scale) as i32)
+                }
+            }
+            .unwrap();
+        }
+    }
+
+    writer.finalize().unwrap();
+    bytes.into_inner()
+}
+
+/// A 440Hz tone at half of full scale.
+fn tone(t: f32) -> f32 {
+    (t * 440.0 * std::f32::consts::TAU).sin() * 0.5
+}
+
+/// Builds an in-memory WAV file alternating one second of a 440Hz tone with half a second of
+/// silence, `seconds` seconds long.
+fn tone_with_pauses(spec: WavSpec, seconds: u32) -> Vec<u8> {
+    wav_from_fn(spec, seconds, |t| if t % 1.5 < 1.0 { tone(t) } else { 0.0 })
+}
+
+fn spec(channels: u16) -> WavSpec {
+    WavSpec {
+        channels,
+        sample_rate: 8000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    }
+}
 
 #[test]
 fn chunking_by_time_works() {
     let _ = tracing_subscriber::fmt()
         .with_max_level(LevelFilter::TRACE)
-        .init();
+        .try_init();
 
     #[cfg(feature = "tracing")]
     tracing::info!("Tracing loaded");
@@ -60,7 +116,7 @@ fn chunking_by_memsize_works() {
 #[cfg(feature = "aiff")]
 #[test]
 fn aiff_decoding_works() {
-    use crate::formats::aiff::AiffSplitter;
+    use dub_oxide::formats::aiff::AiffSplitter;
     let mut aiff_splitter = AiffSplitter::from_file_path("../test_files/test.aiff").unwrap();
 
     let opts = SplitOpts::builder()
@@ -74,3 +130,2263 @@ fn aiff_decoding_works() {
 
     assert_eq!(res.len(), 5);
 }
+
+#[test]
+fn lazy_chunks_match_split_audio() {
+    let bytes = tone_with_pauses(spec(1), 20);
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(4))
+        .build()
+        .unwrap();
+    let lazy = wav_splitter
+        .chunks(opts)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(4))
+        .build()
+        .unwrap();
+    let eager = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(lazy.len(), eager.len());
+    for (lazy, eager) in lazy.iter().zip(eager.iter()) {
+        assert_eq!(&lazy[..], &eager[..]);
+        assert_eq!(lazy.timestamp_start(), eager.timestamp_start());
+        assert_eq!(lazy.timestamp_end(), eager.timestamp_end());
+    }
+
+    let total: usize = lazy.iter().map(|chunk| chunk.len()).sum();
+    assert_eq!(total, 8000 * 20);
+    assert!(lazy.iter().all(|chunk| chunk.len() <= 8000 * 4));
+}
+
+#[test]
+fn lazy_chunks_can_be_consumed_one_at_a_time() {
+    let bytes = tone_with_pauses(spec(1), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .split_by_memsize(8000)
+        .build()
+        .unwrap();
+
+    let mut chunks = wav_splitter.chunks(opts);
+    let first = chunks.next().unwrap().unwrap();
+
+    assert_eq!(first.len(), 8000);
+    assert_eq!(first.timestamp_start(), 0);
+    assert_eq!(first.timestamp_end(), 1000);
+    assert_eq!(chunks.count(), 9);
+}
+
+fn assert_frame_aligned(channels: u16, limit: usize) {
+    let bytes = tone_with_pauses(spec(channels), 12);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_memsize(limit)
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    let channels = channels as usize;
+
+    assert!(res.len() > 1);
+    for chunk in res.iter() {
+        assert_eq!(chunk.len() % channels, 0);
+        assert_eq!(chunk.frames(), chunk.len() / channels);
+    }
+
+    let total_frames: usize = res.iter().map(|chunk| chunk.frames()).sum();
+    assert_eq!(total_frames, 8000 * 12);
+}
+
+#[test]
+fn cuts_are_frame_aligned_for_stereo() {
+    assert_frame_aligned(2, 30_001);
+}
+
+#[test]
+fn cuts_are_frame_aligned_for_three_channels() {
+    assert_frame_aligned(3, 30_001);
+}
+
+#[test]
+fn cuts_are_frame_aligned_for_five_channels() {
+    assert_frame_aligned(5, 40_003);
+}
+
+#[test]
+fn cuts_are_frame_aligned_for_surround() {
+    assert_frame_aligned(6, 50_005);
+}
+
+#[test]
+fn cuts_are_frame_aligned_with_odd_channel_count_and_tiny_limit() {
+    assert_frame_aligned(7, 3);
+}
+
+fn split_with_format<T>(format: SampleFormat, bits_per_sample: u16) -> Vec<usize>
+where
+    T: WavSample,
+{
+    let spec = WavSpec {
+        bits_per_sample,
+        sample_format: format,
+        ..spec(2)
+    };
+    let bytes = tone_with_pauses(spec, 12);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes)
+        .unwrap()
+        .with_sample_type::<T>()
+        .unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(4))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    assert_eq!(res.get_codec().bits_per_sample, bits_per_sample);
+
+    res.iter().map(|chunk| chunk.frames()).collect()
+}
+
+#[test]
+fn every_sample_format_cuts_at_the_same_pauses() {
+    let expected = split_with_format::<i16>(SampleFormat::Int, 16);
+    assert!(expected.len() > 1);
+
+    assert_eq!(split_with_format::<i8>(SampleFormat::Int, 8), expected);
+    assert_eq!(split_with_format::<i32>(SampleFormat::Int, 24), expected);
+    assert_eq!(split_with_format::<i32>(SampleFormat::Int, 32), expected);
+    assert_eq!(split_with_format::<f32>(SampleFormat::Float, 32), expected);
+}
+
+#[test]
+fn chunks_keep_original_bit_depth() {
+    let spec = WavSpec {
+        bits_per_sample: 24,
+        ..spec(1)
+    };
+    let bytes = tone_with_pauses(spec, 3);
+    let original: Vec<i32> = WavReader::new(Cursor::new(&bytes))
+        .unwrap()
+        .samples::<i32>()
+        .map(|x| x.unwrap())
+        .collect();
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes)
+        .unwrap()
+        .with_sample_type::<i32>()
+        .unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .split_by_memsize(5000)
+        .build()
+        .unwrap();
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    let mut out = Cursor::new(Vec::new());
+    let mut writer = WavWriter::new(&mut out, *res.get_codec()).unwrap();
+    for sample in res.iter().flat_map(|chunk| chunk.iter()) {
+        writer.write_sample(*sample).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let written: Vec<i32> = WavReader::new(Cursor::new(out.into_inner()))
+        .unwrap()
+        .samples::<i32>()
+        .map(|x| x.unwrap())
+        .collect();
+
+    assert_eq!(written, original);
+}
+
+#[test]
+fn narrower_sample_type_is_rejected() {
+    let spec = WavSpec {
+        bits_per_sample: 24,
+        ..spec(1)
+    };
+    let bytes = tone_with_pauses(spec, 1);
+
+    let wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    assert!(wav_splitter.with_sample_type::<i16>().is_err());
+
+    let wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    assert!(wav_splitter.with_sample_type::<f32>().is_err());
+}
+
+#[test]
+fn split_into_parts_without_silence_is_even() {
+    let bytes = tone_with_pauses(spec(2), 20);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .split_into_parts(7)
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res.len(), 7);
+    let frames: Vec<usize> = res.iter().map(|chunk| chunk.frames()).collect();
+    assert_eq!(frames.iter().sum::<usize>(), 8000 * 20);
+    assert!(frames.iter().max().unwrap() - frames.iter().min().unwrap() <= 1);
+}
+
+#[test]
+fn split_into_parts_moves_cuts_to_pauses() {
+    let bytes = tone_with_pauses(spec(1), 20);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_into_parts(4)
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res.len(), 4);
+    assert_eq!(
+        res.iter().map(|chunk| chunk.len()).sum::<usize>(),
+        8000 * 20
+    );
+
+    for (chunk, next) in res.iter().zip(res.iter().skip(1)) {
+        // Both sides of every cut should be silent rather than in the middle of the tone.
+        assert!(chunk[chunk.len() - 100..].iter().all(|x| *x == 0));
+        assert!(next[..100].iter().all(|x| *x == 0));
+    }
+}
+
+#[test]
+fn split_into_parts_never_returns_empty_chunks() {
+    let wav_of_frames = |frames: i16| {
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut bytes, spec(1)).unwrap();
+        for frame in 0..frames {
+            writer.write_sample(frame + 1).unwrap();
+        }
+        writer.finalize().unwrap();
+        bytes.into_inner()
+    };
+    let part_frames = |bytes: &[u8], parts: usize| {
+        let mut wav_splitter = WavSplitter::from_bytes(bytes).unwrap();
+        let opts = SplitOpts::builder()
+            .codec(wav_splitter.codec())
+            .split_into_parts(parts)
+            .build()
+            .unwrap();
+
+        wav_splitter
+            .split_audio(opts)
+            .unwrap()
+            .iter()
+            .map(|chunk| chunk.frames())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(part_frames(&wav_of_frames(3), 5), vec![1, 1, 1]);
+    assert_eq!(part_frames(&wav_of_frames(0), 3), Vec::<usize>::new());
+    assert_eq!(part_frames(&wav_of_frames(6), 3), vec![2, 2, 2]);
+}
+
+#[test]
+fn split_into_parts_rejects_other_criteria() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .split_by_memsize(1000)
+        .split_into_parts(4)
+        .build();
+
+    assert!(res.is_err());
+
+    let res = SplitOpts::builder::<WavSpec>().split_into_parts(0).build();
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn overlapping_chunks_share_audio_with_the_previous_chunk() {
+    let bytes = tone_with_pauses(spec(2), 10);
+    let original: Vec<i16> = WavReader::new(Cursor::new(&bytes))
+        .unwrap()
+        .samples::<i16>()
+        .map(|x| x.unwrap())
+        .collect();
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .overlap(Duration::from_millis(500))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    assert!(res.len() > 1);
+
+    assert_eq!(res[0].timestamp_start(), 0);
+    for (prev, chunk) in res.iter().zip(res.iter().skip(1)) {
+        assert_eq!(chunk.timestamp_start(), prev.timestamp_end() - 500);
+        assert_eq!(chunk.core_timestamp_start(), prev.timestamp_end());
+
+        // The first 500ms of each chunk are the last 500ms of the one before it.
+        assert_eq!(&chunk[..8000], &prev[prev.len() - 8000..]);
+    }
+
+    let start = res.last().unwrap().start_frame() * 2;
+    assert_eq!(&res.last().unwrap()[..], &original[start..]);
+
+    let core_ranges = res.core_ranges();
+    assert_eq!(core_ranges.first().unwrap().start, 0);
+    assert_eq!(core_ranges.last().unwrap().end, 80_000);
+    for (prev, range) in core_ranges.iter().zip(core_ranges.iter().skip(1)) {
+        assert_eq!(prev.end, range.start);
+    }
+}
+
+#[test]
+fn overlap_must_be_shorter_than_chunks() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .split_by_duration(Duration::from_secs(1))
+        .overlap(Duration::from_secs(1))
+        .build();
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn chunks_stay_within_min_and_max_duration() {
+    let bytes = tone_with_pauses(spec(2), 30);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(4))
+        .min_duration(Duration::from_millis(3200))
+        .max_duration(Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    assert!(res.len() > 2);
+
+    for chunk in &res[..res.len() - 1] {
+        let duration = chunk.timestamp_end() - chunk.timestamp_start();
+        assert!((3200..=5000).contains(&duration), "{duration}ms");
+        // Every cut should still land in a pause.
+        assert_eq!(chunk[chunk.len() - 1], 0);
+    }
+}
+
+#[test]
+fn max_duration_caps_chunks_without_silence_detection() {
+    let bytes = tone_with_pauses(spec(1), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .split_by_duration(Duration::from_secs(4))
+        .max_duration(Duration::from_secs(3))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    let lens: Vec<usize> = res.iter().map(|chunk| chunk.len()).collect();
+
+    assert_eq!(lens, vec![24_000, 24_000, 24_000, 8_000]);
+}
+
+#[test]
+fn min_duration_must_not_exceed_max_duration() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .split_by_duration(Duration::from_secs(4))
+        .min_duration(Duration::from_secs(3))
+        .max_duration(Duration::from_secs(2))
+        .build();
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn max_duration_must_be_at_least_one_frame() {
+    use dub_oxide::error::Error;
+
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .split_by_duration(Duration::from_secs(4))
+        .max_duration(Duration::from_micros(10))
+        .build();
+
+    assert!(matches!(res, Err(Error::InvalidOption(..))));
+}
+
+#[test]
+fn max_duration_must_be_longer_than_the_overlap() {
+    use dub_oxide::error::Error;
+
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .split_by_duration(Duration::from_millis(500))
+        .overlap(Duration::from_millis(200))
+        .max_duration(Duration::from_millis(100))
+        .build();
+
+    assert!(matches!(res, Err(Error::InvalidOption(..))));
+
+    // Just longer than the overlap still makes progress with every chunk.
+    let bytes = tone_with_pauses(spec(1), 2);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .split_by_duration(Duration::from_millis(500))
+        .overlap(Duration::from_millis(200))
+        .max_duration(Duration::from_millis(250))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    for pair in res.windows(2) {
+        assert!(pair[1].start_frame() > pair[0].start_frame());
+        assert!(pair[0].frames() <= 2000);
+    }
+    assert_eq!(res.last().unwrap().end_frame(), 16_000);
+}
+
+#[test]
+fn chunk_bounds_are_rejected_when_cuts_are_fixed() {
+    use dub_oxide::error::Error;
+
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .split_at(vec![Duration::from_secs(5)])
+        .max_duration(Duration::from_secs(1))
+        .build();
+    assert!(matches!(res, Err(Error::IncompatibleOptions(..))));
+
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .split_into_parts(4)
+        .min_duration(Duration::from_secs(4))
+        .build();
+    assert!(matches!(res, Err(Error::IncompatibleOptions(..))));
+}
+
+#[test]
+fn split_on_silence_returns_each_region() {
+    let bytes = tone_with_pauses(spec(2), 20);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_on_silence(Duration::from_millis(300))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    // 13 full seconds of tone, plus the half second the file ends on.
+    assert_eq!(res.len(), 14);
+    for (i, chunk) in res.iter().enumerate() {
+        let start = i * 1500;
+        assert!(chunk.timestamp_start().abs_diff(start) <= 50);
+        assert!(chunk[2..].iter().any(|x| *x != 0));
+    }
+    for chunk in &res[..res.len() - 1] {
+        let duration = chunk.timestamp_end() - chunk.timestamp_start();
+        assert!(duration.abs_diff(1000) <= 50, "{duration}ms");
+    }
+}
+
+#[test]
+fn split_on_silence_merges_regions_shorter_than_min_duration() {
+    let bytes = tone_with_pauses(spec(1), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_on_silence(Duration::from_millis(300))
+        .min_duration(Duration::from_secs(2))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    let timestamps: Vec<(usize, usize)> = res
+        .iter()
+        .map(|chunk| (chunk.timestamp_start(), chunk.timestamp_end()))
+        .collect();
+
+    // Each pair of regions is kept together with the pause between them.
+    assert_eq!(timestamps.len(), 4);
+    for ((start, end), expected_start) in timestamps.iter().zip([0, 3000, 6000, 9000]) {
+        assert!(start.abs_diff(expected_start) <= 50, "{timestamps:?}");
+        if expected_start < 9000 {
+            assert!(end.abs_diff(expected_start + 2500) <= 50, "{timestamps:?}");
+        }
+    }
+}
+
+#[test]
+fn split_on_silence_keeps_padding() {
+    let bytes = tone_with_pauses(spec(1), 20);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_on_silence(Duration::from_millis(300))
+        .silence_padding(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res.len(), 14);
+    for chunk in &res[1..res.len() - 1] {
+        let duration = chunk.timestamp_end() - chunk.timestamp_start();
+        assert!(duration.abs_diff(1200) <= 100, "{duration}ms");
+        assert!(chunk[..400].iter().all(|x| *x == 0));
+        assert!(chunk[chunk.len() - 400..].iter().all(|x| *x == 0));
+    }
+}
+
+#[test]
+fn split_on_silence_ignores_short_pauses() {
+    let bytes = tone_with_pauses(spec(1), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_on_silence(Duration::from_millis(600))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].len(), 8000 * 10);
+}
+
+#[test]
+fn split_on_silence_needs_a_threshold() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .split_on_silence(Duration::from_millis(300))
+        .build();
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn split_at_cuts_at_the_given_timestamps() {
+    let bytes = tone_with_pauses(spec(2), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .split_at(vec![
+            Duration::from_millis(7700),
+            Duration::from_millis(2300),
+            Duration::from_secs(5),
+            Duration::from_secs(60),
+        ])
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    let timestamps: Vec<(usize, usize)> = res
+        .iter()
+        .map(|chunk| (chunk.timestamp_start(), chunk.timestamp_end()))
+        .collect();
+
+    assert_eq!(
+        timestamps,
+        vec![(0, 2300), (2300, 5000), (5000, 7700), (7700, 10_000)]
+    );
+    assert_eq!(res[0].frames(), 8 * 2300);
+}
+
+#[test]
+fn split_at_snaps_to_nearby_silence() {
+    let bytes = tone_with_pauses(spec(1), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_at(vec![
+            Duration::from_millis(2300),
+            Duration::from_millis(6900),
+        ])
+        .snap_to_silence(Duration::from_millis(400))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res.len(), 3);
+    // Both cuts move forward into the pauses at 2.5s-3s and 7s-7.5s.
+    assert!((2500..3000).contains(&res[0].timestamp_end()));
+    assert!(
+        (7000..7500).contains(&res[1].timestamp_end()),
+        "{}",
+        res[1].timestamp_end()
+    );
+    for (chunk, next) in res.iter().zip(res.iter().skip(1)) {
+        assert_eq!(chunk[chunk.len() - 1], 0);
+        assert_eq!(next[0], 0);
+    }
+}
+
+/// Builds an in-memory 16-bit, 8kHz AIFF file from `samples`.
+#[cfg(feature = "aiff")]
+fn aiff_bytes(channels: u16, samples: &[i16]) -> Vec<u8> {
+    let data: Vec<u8> = samples.iter().flat_map(|x| x.to_be_bytes()).collect();
+
+    let mut comm = Vec::new();
+    comm.extend(channels.to_be_bytes());
+    comm.extend((samples.len() as u32 / channels as u32).to_be_bytes());
+    comm.extend(16u16.to_be_bytes());
+    // 8000Hz as an 80-bit extended float.
+    comm.extend(0x400Bu16.to_be_bytes());
+    comm.extend(0xFA00_0000_0000_0000u64.to_be_bytes());
+
+    let mut body = b"AIFF".to_vec();
+    body.extend(b"COMM");
+    body.extend((comm.len() as u32).to_be_bytes());
+    body.extend(comm);
+    body.extend(b"SSND");
+    body.extend((data.len() as u32 + 8).to_be_bytes());
+    body.extend(0u32.to_be_bytes());
+    body.extend(0u32.to_be_bytes());
+    body.extend(data);
+
+    let mut bytes = b"FORM".to_vec();
+    bytes.extend((body.len() as u32).to_be_bytes());
+    bytes.extend(body);
+    bytes
+}
+
+#[cfg(feature = "aiff")]
+#[test]
+fn aiff_split_at_matches_wav() {
+    use dub_oxide::formats::aiff::AiffSplitter;
+
+    let wav = tone_with_pauses(spec(2), 10);
+    let samples: Vec<i16> = WavReader::new(Cursor::new(&wav))
+        .unwrap()
+        .samples::<i16>()
+        .map(|x| x.unwrap())
+        .collect();
+
+    let mut aiff_splitter = AiffSplitter::from_u8_bytes(aiff_bytes(2, &samples)).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(aiff_splitter.codec())
+        .split_at(vec![Duration::from_millis(2300), Duration::from_secs(5)])
+        .build()
+        .unwrap();
+
+    let res = aiff_splitter.split_audio(opts).unwrap();
+    let timestamps: Vec<(usize, usize)> = res
+        .iter()
+        .map(|chunk| (chunk.timestamp_start(), chunk.timestamp_end()))
+        .collect();
+
+    assert_eq!(timestamps, vec![(0, 2300), (2300, 5000), (5000, 10_000)]);
+    assert_eq!(res[0].len(), 8 * 2300 * 2 * 2);
+}
+
+#[cfg(feature = "aiff")]
+#[test]
+fn aiff_split_at_rejects_snapping() {
+    use dub_oxide::{error::Error, formats::aiff::AiffSplitter};
+
+    let mut aiff_splitter = AiffSplitter::from_u8_bytes(aiff_bytes(1, &[0; 8000 * 4])).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(aiff_splitter.codec())
+        .silence_threshold(-30.0)
+        .split_at(vec![Duration::from_millis(1100)])
+        .snap_to_silence(Duration::from_millis(400))
+        .build()
+        .unwrap();
+
+    let res = aiff_splitter.split_audio(opts);
+
+    assert!(matches!(res, Err(Error::Unsupported(_))));
+}
+
+#[cfg(feature = "aiff")]
+#[test]
+fn aiff_rejects_options_that_need_silence_detection() {
+    use dub_oxide::{error::Error, formats::aiff::AiffSplitter};
+
+    let bytes = aiff_bytes(1, &[0; 8000 * 4]);
+    let split = |options: fn(SplitOptsBuilder<_>) -> SplitOptsBuilder<_>| {
+        let mut aiff_splitter = AiffSplitter::from_u8_bytes(bytes.clone()).unwrap();
+        let opts = options(SplitOpts::builder().codec(aiff_splitter.codec()))
+            .split_into_parts(2)
+            .build()
+            .unwrap();
+
+        aiff_splitter.split_audio(opts)
+    };
+
+    let rejected: [fn(SplitOptsBuilder<_>) -> SplitOptsBuilder<_>; 5] = [
+        |x| {
+            x.silence_threshold(-30.0)
+                .cut_policy(CutPolicy::LongestSilence)
+        },
+        |x| x.voice_activity(VoiceActivityDetector::new()),
+        |x| x.auto_silence_threshold(),
+        |x| {
+            x.silence_threshold(-30.0)
+                .min_silence_duration(Duration::from_millis(300))
+        },
+        |x| {
+            x.silence_threshold(-30.0)
+                .channel_analysis(ChannelAnalysis::AllSilent)
+        },
+    ];
+    for options in rejected {
+        assert!(matches!(split(options), Err(Error::Unsupported(_))));
+    }
+
+    // The silence threshold on its own is documented as ignored.
+    let res = split(|x| x.silence_threshold(-30.0)).unwrap();
+    assert_eq!(res.len(), 2);
+}
+
+#[cfg(feature = "aiff")]
+#[test]
+fn aiff_split_by_duration_matches_wav() {
+    use dub_oxide::formats::aiff::AiffSplitter;
+
+    let samples: Vec<i16> = (0..8000 * 2 * 10).map(|x| (x % 100) as i16).collect();
+    let mut aiff_splitter = AiffSplitter::from_u8_bytes(aiff_bytes(2, &samples)).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(aiff_splitter.codec())
+        .split_by_duration(Duration::from_secs(2))
+        .build()
+        .unwrap();
+
+    let res = aiff_splitter.split_audio(opts).unwrap();
+    let timestamps: Vec<(usize, usize)> = res
+        .iter()
+        .map(|chunk| (chunk.timestamp_start(), chunk.timestamp_end()))
+        .collect();
+
+    assert_eq!(
+        timestamps,
+        vec![
+            (0, 2000),
+            (2000, 4000),
+            (4000, 6000),
+            (6000, 8000),
+            (8000, 10_000)
+        ]
+    );
+}
+
+#[test]
+fn timestamps_do_not_drift_at_44_1khz() {
+    let spec = WavSpec {
+        sample_rate: 44100,
+        ..spec(1)
+    };
+    let bytes = tone_with_pauses(spec, 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .split_by_duration(Duration::from_millis(10))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res.len(), 1000);
+    for (i, chunk) in res.iter().enumerate() {
+        assert_eq!(chunk.len(), 441);
+        assert_eq!(chunk.start_frame(), i * 441);
+        assert_eq!(chunk.start(), Duration::from_millis(10 * i as u64));
+        assert_eq!(chunk.duration(), Duration::from_millis(10));
+    }
+    assert_eq!(res.last().unwrap().end(), Duration::from_secs(10));
+}
+
+#[test]
+fn sample_rates_below_1khz_are_supported() {
+    let spec = WavSpec {
+        sample_rate: 500,
+        ..spec(2)
+    };
+    let bytes = tone_with_pauses(spec, 6);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    let total: usize = res.iter().map(|chunk| chunk.frames()).sum();
+    assert_eq!(total, 3000);
+    assert!(
+        res.iter()
+            .all(|chunk| chunk.duration() <= Duration::from_secs(2))
+    );
+    assert_eq!(res.last().unwrap().end(), Duration::from_secs(6));
+}
+
+/// A tone with silent gaps of 100ms at 0.5s, 400ms at 1s and 100ms at 2.6s.
+fn tone_with_uneven_pauses(t: f32) -> f32 {
+    let silent = (0.5..0.6).contains(&t) || (1.0..1.4).contains(&t) || (2.6..2.7).contains(&t);
+
+    if silent { 0.0 } else { tone(t) }
+}
+
+fn first_cut(bytes: &[u8], policy: Option<CutPolicy>) -> (Duration, CutReason) {
+    let mut wav_splitter = WavSplitter::from_bytes(bytes).unwrap();
+
+    let mut opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-40.0)
+        .split_by_duration(Duration::from_secs(3));
+    if let Some(policy) = policy {
+        opts = opts.cut_policy(policy);
+    }
+
+    let first = wav_splitter
+        .chunks(opts.build().unwrap())
+        .next()
+        .unwrap()
+        .unwrap();
+
+    (first.end(), first.cut_reason())
+}
+
+#[test]
+fn cut_policies_pick_different_pauses() {
+    let bytes = wav_from_fn(spec(1), 4, tone_with_uneven_pauses);
+
+    let (end, reason) = first_cut(&bytes, None);
+    assert!(
+        (Duration::from_millis(2600)..Duration::from_millis(2700)).contains(&end),
+        "{end:?}"
+    );
+    assert_eq!(reason, CutReason::Policy(CutPolicy::LastSilence));
+
+    let (end, reason) = first_cut(&bytes, Some(CutPolicy::LongestSilence));
+    assert!(
+        (Duration::from_millis(1150)..Duration::from_millis(1250)).contains(&end),
+        "{end:?}"
+    );
+    assert_eq!(reason, CutReason::Policy(CutPolicy::LongestSilence));
+
+    let (end, reason) = first_cut(&bytes, Some(CutPolicy::ClosestToTarget));
+    assert!(
+        (Duration::from_millis(2600)..Duration::from_millis(2700)).contains(&end),
+        "{end:?}"
+    );
+    assert_eq!(reason, CutReason::Policy(CutPolicy::ClosestToTarget));
+}
+
+#[test]
+fn quietest_window_is_used_when_nothing_is_silent() {
+    // Never silent, but much quieter between 2.5s and 2.7s.
+    let bytes = wav_from_fn(spec(1), 4, |t| {
+        if (2.5..2.7).contains(&t) {
+            tone(t) * 0.1
+        } else {
+            tone(t)
+        }
+    });
+
+    let (end, reason) = first_cut(&bytes, None);
+    assert_eq!(end, Duration::from_secs(3));
+    assert_eq!(reason, CutReason::Target);
+
+    let (end, reason) = first_cut(&bytes, Some(CutPolicy::QuietestWindow));
+    assert!(
+        (Duration::from_millis(2500)..Duration::from_millis(2700)).contains(&end),
+        "{end:?}"
+    );
+    assert_eq!(reason, CutReason::Policy(CutPolicy::QuietestWindow));
+}
+
+#[test]
+fn last_chunk_reports_end_of_input() {
+    let bytes = tone_with_pauses(spec(1), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    let (last, rest) = res.split_last().unwrap();
+
+    assert_eq!(last.cut_reason(), CutReason::EndOfInput);
+    assert!(
+        rest.iter()
+            .all(|chunk| chunk.cut_reason() == CutReason::Policy(CutPolicy::LastSilence))
+    );
+}
+
+#[test]
+fn cut_policy_cannot_be_used_with_split_on_silence() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .silence_threshold(-20.0)
+        .split_on_silence(Duration::from_millis(300))
+        .cut_policy(CutPolicy::LongestSilence)
+        .build();
+
+    assert!(res.is_err());
+}
+
+fn first_cut_with(
+    bytes: &[u8],
+    configure: impl Fn(SplitOptsBuilder<WavSpec>) -> SplitOptsBuilder<WavSpec>,
+) -> Duration {
+    let mut wav_splitter = WavSplitter::from_bytes(bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-40.0)
+        .split_by_duration(Duration::from_secs(3));
+    let opts = configure(opts).build().unwrap();
+
+    let first = wav_splitter.chunks(opts).next().unwrap().unwrap();
+
+    first.end()
+}
+
+#[test]
+fn min_silence_duration_ignores_short_gaps() {
+    // A 400ms pause at 1s, then a 60ms gap between "syllables" at 2.5s.
+    let bytes = wav_from_fn(spec(1), 4, |t| {
+        if (1.0..1.4).contains(&t) || (2.5..2.56).contains(&t) {
+            0.0
+        } else {
+            tone(t)
+        }
+    });
+
+    let end = first_cut_with(&bytes, |opts| opts);
+    assert!(
+        (Duration::from_millis(2500)..Duration::from_millis(2560)).contains(&end),
+        "{end:?}"
+    );
+
+    // The cut lands in the middle of the long pause instead.
+    let end = first_cut_with(&bytes, |opts| {
+        opts.min_silence_duration(Duration::from_millis(200))
+    });
+    assert_eq!(end, Duration::from_millis(1200));
+}
+
+#[test]
+fn cuts_move_past_the_pause_the_previous_chunk_was_cut_in() {
+    // 1s of tone then 0.5s of silence, over and over.
+    let bytes = tone_with_pauses(spec(2), 10);
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-30.0)
+        .split_by_duration(Duration::from_secs(1))
+        .build()
+        .unwrap();
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    let (last, rest) = res.split_last().unwrap();
+    assert_eq!(last.end(), Duration::from_secs(10));
+    for chunk in rest {
+        assert!(
+            chunk.duration() >= Duration::from_millis(500),
+            "{:?}..{:?}",
+            chunk.start(),
+            chunk.end()
+        );
+        // Each pause is 8000..12000 frames into every 1.5s cycle.
+        assert!(
+            chunk.end_frame() % 12_000 >= 8_000 || chunk.end_frame() % 12_000 == 0,
+            "cut mid-tone at {:?}",
+            chunk.end()
+        );
+    }
+}
+
+#[test]
+fn exit_threshold_keeps_a_flickering_pause_together() {
+    // Between 1s and 1.6s the level flickers between silence and a very quiet tone every 50ms.
+    let bytes = wav_from_fn(spec(1), 4, |t| {
+        if !(1.0..1.6).contains(&t) {
+            tone(t)
+        } else if ((t / 0.05) as u32).is_multiple_of(2) {
+            0.0
+        } else {
+            tone(t) * 0.04
+        }
+    });
+
+    let end = first_cut_with(&bytes, |opts| {
+        opts.min_silence_duration(Duration::from_millis(300))
+    });
+    assert_eq!(end, Duration::from_secs(3));
+
+    let end = first_cut_with(&bytes, |opts| {
+        opts.min_silence_duration(Duration::from_millis(300))
+            .silence_exit_threshold(-30.0)
+    });
+    assert_eq!(end, Duration::from_millis(1300));
+}
+
+#[test]
+fn exit_threshold_must_not_be_below_silence_threshold() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .silence_threshold(-20.0)
+        .silence_exit_threshold(-30.0)
+        .split_by_duration(Duration::from_secs(2))
+        .build();
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn shorter_analysis_window_finds_short_gaps() {
+    // A 30ms gap, shorter than the default 50ms window.
+    let bytes = wav_from_fn(spec(1), 4, |t| {
+        if (2.5..2.53).contains(&t) {
+            0.0
+        } else {
+            tone(t)
+        }
+    });
+
+    let end = first_cut_with(&bytes, |opts| opts);
+    assert_eq!(end, Duration::from_secs(3));
+
+    let end = first_cut_with(&bytes, |opts| {
+        opts.analysis_window(Duration::from_millis(20))
+    });
+    assert!(
+        (Duration::from_millis(2500)..Duration::from_millis(2530)).contains(&end),
+        "{end:?}"
+    );
+}
+
+#[test]
+fn overlapping_windows_find_gaps_between_window_boundaries() {
+    // A 60ms gap that straddles the boundary between two 50ms windows.
+    let bytes = wav_from_fn(spec(1), 4, |t| {
+        if (2.52..2.58).contains(&t) {
+            0.0
+        } else {
+            tone(t)
+        }
+    });
+
+    let end = first_cut_with(&bytes, |opts| opts);
+    assert_eq!(end, Duration::from_secs(3));
+
+    let end = first_cut_with(&bytes, |opts| opts.analysis_hop(Duration::from_millis(10)));
+    assert!(
+        (Duration::from_millis(2520)..Duration::from_millis(2580)).contains(&end),
+        "{end:?}"
+    );
+}
+
+#[test]
+fn split_on_silence_works_with_overlapping_windows() {
+    let bytes = tone_with_pauses(spec(1), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_on_silence(Duration::from_millis(300))
+        .analysis_window(Duration::from_millis(40))
+        .analysis_hop(Duration::from_millis(10))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res.len(), 7);
+    for chunk in res.iter() {
+        assert!(chunk.duration() <= Duration::from_millis(1050));
+    }
+}
+
+#[test]
+fn analysis_hop_must_not_be_longer_than_the_window() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .analysis_window(Duration::from_millis(20))
+        .analysis_hop(Duration::from_millis(30))
+        .build();
+
+    assert!(res.is_err());
+}
+
+/// Quiet pseudo-random hiss at roughly -50dBFS.
+fn hiss(t: f32) -> f32 {
+    let mut x = (t * 8000.0) as u32;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85eb_ca6b);
+    x ^= x >> 13;
+    x = x.wrapping_mul(0xc2b2_ae35);
+    x ^= x >> 16;
+
+    (x as f32 / u32::MAX as f32 * 2.0 - 1.0) * 0.005
+}
+
+#[test]
+fn auto_silence_threshold_copes_with_background_hiss() {
+    let bytes = wav_from_fn(spec(1), 10, |t| {
+        let signal = if t % 1.5 < 1.0 { tone(t) } else { 0.0 };
+        signal + hiss(t)
+    });
+
+    // The hiss is louder than a threshold picked for a clean recording, so no pauses are found.
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-60.0)
+        .split_on_silence(Duration::from_millis(300))
+        .build()
+        .unwrap();
+    let res = wav_splitter.split_audio(opts).unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res.silence_threshold(), Some(-60.0));
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .auto_silence_threshold()
+        .split_on_silence(Duration::from_millis(300))
+        .build()
+        .unwrap();
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res.len(), 7);
+    let threshold = res.silence_threshold().unwrap();
+    assert!((-55.0..-40.0).contains(&threshold), "{threshold}");
+}
+
+#[test]
+fn auto_silence_threshold_rewinds_before_splitting() {
+    let bytes = tone_with_pauses(spec(2), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .auto_silence_threshold()
+        .split_by_duration(Duration::from_secs(2))
+        .build()
+        .unwrap();
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    let total: usize = res.iter().map(|chunk| chunk.len()).sum();
+    assert_eq!(total, 8000 * 2 * 10);
+    assert_eq!(res[0].start_frame(), 0);
+    assert_eq!(
+        res[0].cut_reason(),
+        CutReason::Policy(CutPolicy::LastSilence)
+    );
+}
+
+#[test]
+fn auto_silence_threshold_conflicts_with_a_fixed_threshold() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .silence_threshold(-20.0)
+        .auto_silence_threshold()
+        .split_by_duration(Duration::from_secs(2))
+        .build();
+
+    assert!(res.is_err());
+}
+
+/// A 150Hz buzz with a few harmonics, a rough stand-in for a voiced vowel.
+fn voice(t: f32) -> f32 {
+    (1..=4)
+        .map(|h| (t * 150.0 * h as f32 * std::f32::consts::TAU).sin() * 0.3 / h as f32)
+        .sum()
+}
+
+#[test]
+fn voice_activity_splits_speech_over_loud_noise() {
+    // One second of "speech" then half a second of noise that's nearly as loud.
+    let bytes = wav_from_fn(spec(1), 9, |t| {
+        if t % 1.5 < 1.0 {
+            voice(t)
+        } else {
+            hiss(t) * 60.0
+        }
+    });
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-30.0)
+        .split_on_silence(Duration::from_millis(300))
+        .build()
+        .unwrap();
+    let res = wav_splitter.split_audio(opts).unwrap();
+    assert_eq!(res.len(), 1);
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .voice_activity(VoiceActivityDetector::new())
+        .split_on_silence(Duration::from_millis(300))
+        .build()
+        .unwrap();
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res.len(), 6);
+    for (i, chunk) in res.iter().enumerate() {
+        let start = Duration::from_millis(1500 * i as u64);
+        assert_eq!(chunk.start(), start);
+        assert_eq!(chunk.duration(), Duration::from_secs(1));
+    }
+}
+
+#[test]
+fn voice_activity_can_pick_cuts_by_duration() {
+    let bytes = wav_from_fn(spec(1), 9, |t| {
+        if t % 1.5 < 1.0 {
+            voice(t)
+        } else {
+            hiss(t) * 60.0
+        }
+    });
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .voice_activity(VoiceActivityDetector::new())
+        .split_by_duration(Duration::from_secs(2))
+        .build()
+        .unwrap();
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    let (last, rest) = res.split_last().unwrap();
+    assert_eq!(last.cut_reason(), CutReason::EndOfInput);
+    for chunk in rest {
+        // Every cut lands in the noise between two bursts of speech.
+        let end = chunk.end().as_secs_f32() % 1.5;
+        assert!((1.0..1.5).contains(&end), "{:?}", chunk.end());
+    }
+}
+
+#[test]
+fn voice_activity_conflicts_with_a_silence_threshold() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .silence_threshold(-20.0)
+        .voice_activity(VoiceActivityDetector::new())
+        .split_by_duration(Duration::from_secs(2))
+        .build();
+
+    assert!(res.is_err());
+}
+
+/// Two speakers taking turns on separate channels of a stereo file, with a gap between turns.
+fn interview() -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    let mut writer = WavWriter::new(&mut bytes, spec(2)).unwrap();
+
+    for frame in 0..8000 * 6 {
+        let t = frame as f32 / 8000.0;
+        let left = if t % 3.0 < 1.0 { tone(t) } else { 0.0 };
+        let right = if (1.5..2.5).contains(&(t % 3.0)) {
+            tone(t)
+        } else {
+            0.0
+        };
+
+        writer
+            .write_sample((left * i16::MAX as f32) as i16)
+            .unwrap();
+        writer
+            .write_sample((right * i16::MAX as f32) as i16)
+            .unwrap();
+    }
+
+    writer.finalize().unwrap();
+    bytes.into_inner()
+}
+
+fn interview_regions(analysis: ChannelAnalysis) -> Vec<(Duration, Duration)> {
+    let bytes = interview();
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_on_silence(Duration::from_millis(300))
+        .channel_analysis(analysis)
+        .build()
+        .unwrap();
+
+    wav_splitter
+        .split_audio(opts)
+        .unwrap()
+        .iter()
+        .map(|chunk| (chunk.start(), chunk.end()))
+        .collect()
+}
+
+#[test]
+fn all_silent_only_cuts_between_turns() {
+    let secs = Duration::from_secs_f32;
+    let turns = vec![
+        (secs(0.0), secs(1.0)),
+        (secs(1.5), secs(2.5)),
+        (secs(3.0), secs(4.0)),
+        (secs(4.5), secs(5.5)),
+    ];
+
+    assert_eq!(interview_regions(ChannelAnalysis::AllSilent), turns);
+    assert_eq!(interview_regions(ChannelAnalysis::Mixdown), turns);
+}
+
+#[test]
+fn any_silent_treats_one_quiet_speaker_as_silence() {
+    // Someone is always quiet, so there's never anything to keep.
+    assert!(interview_regions(ChannelAnalysis::AnySilent).is_empty());
+}
+
+#[test]
+fn selected_channels_ignore_the_other_speaker() {
+    let secs = Duration::from_secs_f32;
+
+    assert_eq!(
+        interview_regions(ChannelAnalysis::Channels(vec![1])),
+        vec![(secs(1.5), secs(2.5)), (secs(4.5), secs(5.5))]
+    );
+}
+
+#[test]
+fn mixdown_cancels_out_of_phase_channels() {
+    let mut bytes = Cursor::new(Vec::new());
+    let mut writer = WavWriter::new(&mut bytes, spec(2)).unwrap();
+    for frame in 0..8000 * 4 {
+        let sample = (tone(frame as f32 / 8000.0) * i16::MAX as f32) as i16;
+        writer.write_sample(sample).unwrap();
+        writer.write_sample(-sample).unwrap();
+    }
+    writer.finalize().unwrap();
+    let bytes = bytes.into_inner();
+
+    let cut = |analysis: ChannelAnalysis| {
+        let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+        let opts = SplitOpts::builder()
+            .codec(wav_splitter.codec())
+            .silence_threshold(-20.0)
+            .split_by_duration(Duration::from_secs(3))
+            .channel_analysis(analysis)
+            .build()
+            .unwrap();
+
+        wav_splitter
+            .chunks(opts)
+            .next()
+            .unwrap()
+            .unwrap()
+            .cut_reason()
+    };
+
+    assert_eq!(
+        cut(ChannelAnalysis::Mixdown),
+        CutReason::Policy(CutPolicy::LastSilence)
+    );
+    assert_eq!(cut(ChannelAnalysis::AllSilent), CutReason::Target);
+}
+
+#[test]
+fn selected_channels_must_exist() {
+    let res = SplitOpts::builder()
+        .codec(spec(2))
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .channel_analysis(ChannelAnalysis::Channels(vec![2]))
+        .build();
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn trim_silence_keeps_only_a_little_silence_around_each_chunk() {
+    let bytes = tone_with_pauses(spec(1), 10);
+    let original: Vec<i16> = WavReader::new(Cursor::new(&bytes))
+        .unwrap()
+        .samples::<i16>()
+        .map(|x| x.unwrap())
+        .collect();
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .trim_silence(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    assert!(res.len() > 2);
+
+    // Each pause is cut in the middle, leaving 250ms of silence either side before trimming.
+    assert_eq!(res[0].start(), Duration::ZERO);
+    assert!(res[0].end() > Duration::from_millis(1000));
+    assert!(res[0].end() <= Duration::from_millis(1150));
+    assert!(res[1].start() >= Duration::from_millis(1350));
+    assert!(res[1].start() < Duration::from_millis(1500));
+
+    for chunk in res.iter() {
+        assert!(chunk.duration() <= Duration::from_millis(1200));
+        assert_eq!(chunk.frames(), chunk.end_frame() - chunk.start_frame());
+        assert_eq!(
+            &chunk[..],
+            &original[chunk.start_frame()..chunk.end_frame()]
+        );
+    }
+}
+
+#[test]
+fn trim_silence_shortens_chunks_that_are_silent_throughout() {
+    // A 4s pause between 2s and 6s, so some 1s chunks fall entirely inside it.
+    let bytes = wav_from_fn(spec(1), 10, |t| {
+        if (2.0..6.0).contains(&t) {
+            0.0
+        } else {
+            tone(t)
+        }
+    });
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(1))
+        .trim_silence(Duration::from_millis(10))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    let silent: Vec<_> = res
+        .iter()
+        .filter(|chunk| chunk.start() >= Duration::from_millis(2100))
+        .filter(|chunk| chunk.end() <= Duration::from_millis(5900))
+        .collect();
+
+    assert!(silent.len() >= 2);
+    for chunk in silent {
+        assert!(
+            chunk.duration() <= Duration::from_millis(10),
+            "{:?}",
+            chunk.duration()
+        );
+        // Silent chunks are only flagged when asked for.
+        assert!(!chunk.is_silent());
+    }
+}
+
+#[test]
+fn trim_silence_cannot_be_used_with_overlap() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .overlap(Duration::from_millis(500))
+        .trim_silence(Duration::from_millis(100))
+        .build();
+
+    assert!(res.is_err());
+}
+
+/// Splits two seconds of tone, four seconds of silence and four more seconds of tone into one
+/// second chunks.
+fn split_with_long_pause(silent_chunks: SilentChunks) -> Vec<(Duration, Duration, bool)> {
+    let bytes = wav_from_fn(spec(1), 10, |t| {
+        if (2.0..6.0).contains(&t) {
+            0.0
+        } else {
+            tone(t)
+        }
+    });
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(1))
+        .silent_chunks(silent_chunks)
+        .build()
+        .unwrap();
+
+    wav_splitter
+        .split_audio(opts)
+        .unwrap()
+        .iter()
+        .map(|x| (x.start(), x.end(), x.is_silent()))
+        .collect()
+}
+
+#[test]
+fn silent_chunks_are_flagged() {
+    let chunks = split_with_long_pause(SilentChunks::Flag);
+    let silent: Vec<_> = chunks.iter().filter(|(_, _, silent)| *silent).collect();
+
+    assert!(silent.len() >= 2);
+    for (start, end, _) in silent {
+        assert!(*start >= Duration::from_secs(2));
+        assert!(*end <= Duration::from_secs(6));
+    }
+
+    assert!(
+        split_with_long_pause(SilentChunks::Keep)
+            .iter()
+            .all(|(_, _, silent)| !silent)
+    );
+}
+
+#[test]
+fn silent_chunks_can_be_dropped() {
+    let flagged = split_with_long_pause(SilentChunks::Flag);
+    let dropped = split_with_long_pause(SilentChunks::Drop);
+
+    let audible: Vec<_> = flagged
+        .into_iter()
+        .filter(|(_, _, silent)| !silent)
+        .collect();
+    assert_eq!(dropped, audible);
+}
+
+#[test]
+fn dropping_silent_chunks_needs_a_threshold() {
+    let res = SplitOpts::builder()
+        .codec(spec(1))
+        .split_by_duration(Duration::from_secs(1))
+        .silent_chunks(SilentChunks::Drop)
+        .build();
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn chunks_carry_their_levels_and_cut_details() {
+    let bytes = tone_with_pauses(spec(2), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    assert!(res.len() > 2);
+
+    for (i, chunk) in res.iter().enumerate() {
+        assert_eq!(chunk.index(), i);
+        // The tone peaks at half of full scale, and is silent for a third of the time.
+        assert!((chunk.peak_dbfs() + 6.0).abs() < 0.1);
+        assert!(chunk.dbfs() < -9.0 && chunk.dbfs() > -14.0);
+    }
+
+    let (last, rest) = res.split_last().unwrap();
+    for chunk in rest {
+        assert!(chunk.is_cut_at_silence());
+        assert!(chunk.cut_level().unwrap() <= -20.0);
+    }
+    assert!(!last.is_cut_at_silence());
+    assert_eq!(last.cut_level(), None);
+}
+
+#[test]
+fn hard_limit_cuts_report_the_level_at_the_cut() {
+    let bytes = wav_from_fn(spec(1), 5, tone);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(res[0].cut_reason(), CutReason::Target);
+    assert!(!res[0].is_cut_at_silence());
+    // A sine wave at half of full scale has an RMS level of about -9dBFS.
+    assert!((res[0].cut_level().unwrap() + 9.0).abs() < 0.5);
+}
+
+#[cfg(feature = "aiff")]
+#[test]
+fn aiff_chunk_levels_match_wav() {
+    use dub_oxide::formats::aiff::AiffSplitter;
+
+    let wav = tone_with_pauses(spec(2), 10);
+    let samples: Vec<i16> = WavReader::new(Cursor::new(&wav))
+        .unwrap()
+        .samples::<i16>()
+        .map(|x| x.unwrap())
+        .collect();
+    let cuts = vec![Duration::from_millis(2300), Duration::from_secs(5)];
+
+    let mut aiff_splitter = AiffSplitter::from_u8_bytes(aiff_bytes(2, &samples)).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(aiff_splitter.codec())
+        .split_at(cuts.clone())
+        .build()
+        .unwrap();
+    let aiff = aiff_splitter.split_audio(opts).unwrap();
+
+    let mut wav_splitter = WavSplitter::from_bytes(&wav).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .split_at(cuts)
+        .build()
+        .unwrap();
+    let wav = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(aiff.len(), wav.len());
+    for (aiff, wav) in aiff.iter().zip(wav.iter()) {
+        assert_eq!(aiff.index(), wav.index());
+        assert!((aiff.peak() - wav.peak()).abs() < 1e-6);
+        assert!((aiff.rms() - wav.rms()).abs() < 1e-4);
+    }
+}
+
+fn pause_opts(codec: WavSpec) -> SplitOpts {
+    SplitOpts::builder()
+        .codec(codec)
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn plan_matches_split_audio() {
+    let bytes = tone_with_pauses(spec(2), 10);
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let expected = wav_splitter
+        .split_audio(pause_opts(wav_splitter.codec()))
+        .unwrap();
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let plan = wav_splitter.plan(pause_opts(wav_splitter.codec())).unwrap();
+
+    assert_eq!(plan.len(), expected.len());
+    for (planned, chunk) in plan.iter().zip(expected.iter()) {
+        assert_eq!(planned, &chunk.to_planned());
+    }
+    assert_eq!(
+        plan.cuts(),
+        expected[..expected.len() - 1]
+            .iter()
+            .map(|x| x.end())
+            .collect::<Vec<_>>()
+    );
+
+    let res = wav_splitter.apply(plan).unwrap();
+    assert_eq!(res.len(), expected.len());
+    for (chunk, expected) in res.iter().zip(expected.iter()) {
+        assert_eq!(&chunk[..], &expected[..]);
+        assert_eq!(chunk.index(), expected.index());
+        assert_eq!(chunk.start_frame(), expected.start_frame());
+        assert_eq!(chunk.cut_reason(), expected.cut_reason());
+        assert_eq!(chunk.rms(), expected.rms());
+    }
+}
+
+#[test]
+fn adjusted_plan_is_applied() {
+    let bytes = tone_with_pauses(spec(2), 10);
+    let original: Vec<i16> = WavReader::new(Cursor::new(&bytes))
+        .unwrap()
+        .samples::<i16>()
+        .map(|x| x.unwrap())
+        .collect();
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let mut plan = wav_splitter.plan(pause_opts(wav_splitter.codec())).unwrap();
+    let second = plan.chunks_mut().remove(1);
+    plan.chunks_mut()[0] = PlannedChunk::new(8000, 0, second.end_frame());
+
+    let res = wav_splitter.apply(plan.clone()).unwrap();
+    assert_eq!(res.len(), plan.len());
+    assert_eq!(res[0].end_frame(), second.end_frame());
+    assert_eq!(&res[0][..], &original[..second.end_frame() * 2]);
+    for chunk in res.iter() {
+        assert_eq!(
+            &chunk[..],
+            &original[chunk.start_frame() * 2..chunk.end_frame() * 2]
+        );
+    }
+}
+
+#[test]
+fn plan_past_the_end_is_rejected() {
+    let bytes = tone_with_pauses(spec(1), 2);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let plan = SplitPlan::new(vec![PlannedChunk::new(8000, 8000, 24_000)]);
+
+    assert!(wav_splitter.apply(plan).is_err());
+}
+
+#[cfg(feature = "aiff")]
+#[test]
+fn aiff_plan_can_be_applied() {
+    use dub_oxide::formats::aiff::AiffSplitter;
+
+    let wav = tone_with_pauses(spec(2), 10);
+    let samples: Vec<i16> = WavReader::new(Cursor::new(&wav))
+        .unwrap()
+        .samples::<i16>()
+        .map(|x| x.unwrap())
+        .collect();
+    let opts = |codec| {
+        SplitOpts::builder()
+            .codec(codec)
+            .split_by_duration(Duration::from_secs(3))
+            .build()
+            .unwrap()
+    };
+
+    let mut aiff_splitter = AiffSplitter::from_u8_bytes(aiff_bytes(2, &samples)).unwrap();
+    let expected = aiff_splitter
+        .split_audio(opts(aiff_splitter.codec()))
+        .unwrap();
+
+    let mut aiff_splitter = AiffSplitter::from_u8_bytes(aiff_bytes(2, &samples)).unwrap();
+    let plan = aiff_splitter.plan(opts(aiff_splitter.codec())).unwrap();
+    let res = aiff_splitter.apply(plan).unwrap();
+
+    assert_eq!(res.len(), expected.len());
+    for (chunk, expected) in res.iter().zip(expected.iter()) {
+        assert_eq!(&chunk[..], &expected[..]);
+        assert_eq!(chunk.to_planned(), expected.to_planned());
+    }
+}
+
+#[cfg(feature = "aiff")]
+#[test]
+fn aiff_plan_works_out_cuts_without_the_audio() {
+    use dub_oxide::formats::aiff::AiffSplitter;
+
+    let mut aiff_splitter =
+        AiffSplitter::from_u8_bytes(aiff_bytes(2, &[0; 8000 * 2 * 10])).unwrap();
+    let opts = SplitOpts::builder()
+        .codec(aiff_splitter.codec())
+        .split_by_duration(Duration::from_secs(3))
+        .overlap(Duration::from_millis(500))
+        .build()
+        .unwrap();
+
+    let plan = aiff_splitter.plan(opts).unwrap();
+    let frames: Vec<_> = plan
+        .iter()
+        .map(|x| {
+            (
+                x.start_frame(),
+                x.end_frame(),
+                x.core_range(),
+                x.cut_reason(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        frames,
+        vec![
+            (0, 24_000, 0..24_000, CutReason::Target),
+            (20_000, 48_000, 24_000..48_000, CutReason::Target),
+            (44_000, 72_000, 48_000..72_000, CutReason::Target),
+            (68_000, 80_000, 72_000..80_000, CutReason::EndOfInput),
+        ]
+    );
+}
+
+/// The two sides of [`interview`] as separate stems, the first mono and the second stereo.
+fn interview_stems() -> (Vec<u8>, Vec<u8>) {
+    let first = wav_from_fn(spec(1), 6, |t| if t % 3.0 < 1.0 { tone(t) } else { 0.0 });
+    let second = wav_from_fn(spec(2), 6, |t| {
+        if (1.5..2.5).contains(&(t % 3.0)) {
+            tone(t)
+        } else {
+            0.0
+        }
+    });
+
+    (first, second)
+}
+
+#[test]
+fn stems_are_cut_at_the_same_frames() {
+    use dub_oxide::formats::stems::StemSplitter;
+
+    let (first, second) = interview_stems();
+    let stems = vec![
+        WavSplitter::from_bytes(&first).unwrap(),
+        WavSplitter::from_bytes(&second).unwrap(),
+    ];
+    let mut stem_splitter = StemSplitter::new(stems).unwrap();
+    assert_eq!(stem_splitter.codec().channels, 3);
+
+    let opts = SplitOpts::builder()
+        .codec(stem_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .channel_analysis(ChannelAnalysis::AllSilent)
+        .build()
+        .unwrap();
+
+    let results = stem_splitter.split_audio(opts).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].len(), results[1].len());
+    assert!(results[0].len() > 2);
+
+    // Each cut lands where neither speaker is talking.
+    for chunk in results[0].iter().take(results[0].len() - 1) {
+        let t = chunk.end().as_secs_f32() % 3.0;
+        assert!(
+            (1.0..1.5).contains(&t) || t >= 2.5,
+            "cut at {:?}",
+            chunk.end()
+        );
+    }
+
+    for (bytes, res) in [first, second].iter().zip(&results) {
+        let original: Vec<i16> = WavReader::new(Cursor::new(bytes))
+            .unwrap()
+            .samples::<i16>()
+            .map(|x| x.unwrap())
+            .collect();
+        let channels = res.get_codec().channels as usize;
+
+        for (chunk, other) in res.iter().zip(results[0].iter()) {
+            assert_eq!(chunk.start_frame(), other.start_frame());
+            assert_eq!(chunk.end_frame(), other.end_frame());
+            assert_eq!(
+                &chunk[..],
+                &original[chunk.start_frame() * channels..chunk.end_frame() * channels]
+            );
+        }
+    }
+}
+
+#[test]
+fn stems_are_planned_like_one_interleaved_file() {
+    use dub_oxide::formats::stems::StemSplitter;
+
+    let (first, second) = interview_stems();
+    let samples = |bytes: &[u8]| -> Vec<i16> {
+        WavReader::new(Cursor::new(bytes))
+            .unwrap()
+            .samples::<i16>()
+            .map(|x| x.unwrap())
+            .collect()
+    };
+    let (left, rest) = (samples(&first), samples(&second));
+
+    let mut combined = Cursor::new(Vec::new());
+    let mut writer = WavWriter::new(&mut combined, spec(3)).unwrap();
+    for (left, rest) in left.iter().zip(rest.chunks(2)) {
+        for sample in std::iter::once(left).chain(rest) {
+            writer.write_sample(*sample).unwrap();
+        }
+    }
+    writer.finalize().unwrap();
+    let combined = combined.into_inner();
+
+    // Picking the threshold automatically reads through the stems and rewinds them first.
+    let opts = |codec| {
+        SplitOpts::builder()
+            .codec(codec)
+            .auto_silence_threshold()
+            .split_by_duration(Duration::from_secs(2))
+            .channel_analysis(ChannelAnalysis::AllSilent)
+            .build()
+            .unwrap()
+    };
+
+    let mut wav_splitter = WavSplitter::from_bytes(&combined).unwrap();
+    let expected = wav_splitter.plan(opts(wav_splitter.codec())).unwrap();
+
+    let stems = vec![
+        WavSplitter::from_bytes(&first).unwrap(),
+        WavSplitter::from_bytes(&second).unwrap(),
+    ];
+    let mut stem_splitter = StemSplitter::new(stems).unwrap();
+    let plan = stem_splitter.plan(opts(stem_splitter.codec())).unwrap();
+
+    assert!(plan.len() > 2);
+    assert_eq!(plan, expected);
+}
+
+#[test]
+fn stems_must_match() {
+    use dub_oxide::formats::stems::StemSplitter;
+
+    let short = wav_from_fn(spec(1), 5, tone);
+    let long = wav_from_fn(spec(1), 6, tone);
+    let fast = wav_from_fn(
+        WavSpec {
+            sample_rate: 16_000,
+            ..spec(1)
+        },
+        6,
+        tone,
+    );
+
+    let matches = |a: &[u8], b: &[u8]| {
+        StemSplitter::new(vec![
+            WavSplitter::from_bytes(a).unwrap(),
+            WavSplitter::from_bytes(b).unwrap(),
+        ])
+        .is_ok()
+    };
+
+    assert!(matches(&long, &long));
+    assert!(!matches(&short, &long));
+    assert!(!matches(&fast, &long));
+    assert!(StemSplitter::<std::io::Cursor<&[u8]>>::new(vec![]).is_err());
+}
+
+/// Checks that collecting the lazy chunk iterator, which always builds and measures one chunk at a
+/// time, gives exactly the same chunks as `split_audio`, which builds them in parallel with the
+/// `parallel` feature.
+///
+/// Both paths find pauses with the same window analysis, so this doesn't cover that analysis
+/// running in parallel; the silence detector's unit tests check it against measuring each window
+/// in order.
+fn assert_split_matches_lazy(
+    bytes: &[u8],
+    configure: impl Fn(SplitOptsBuilder<WavSpec>) -> SplitOptsBuilder<WavSpec>,
+) {
+    let mut wav_splitter = WavSplitter::from_bytes(bytes).unwrap();
+    let opts = configure(SplitOpts::builder().codec(wav_splitter.codec()))
+        .build()
+        .unwrap();
+    let lazy = wav_splitter
+        .chunks(opts)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let mut wav_splitter = WavSplitter::from_bytes(bytes).unwrap();
+    let opts = configure(SplitOpts::builder().codec(wav_splitter.codec()))
+        .build()
+        .unwrap();
+    let eager = wav_splitter.split_audio(opts).unwrap();
+
+    assert_eq!(lazy.len(), eager.len());
+    for (lazy, eager) in lazy.iter().zip(eager.iter()) {
+        assert_eq!(&lazy[..], &eager[..]);
+        assert_eq!(lazy.to_planned(), eager.to_planned());
+        assert_eq!(lazy.index(), eager.index());
+        assert_eq!(lazy.peak(), eager.peak());
+        assert_eq!(lazy.rms(), eager.rms());
+    }
+}
+
+#[test]
+fn split_audio_matches_the_lazy_iterator_for_every_mode() {
+    let bytes = tone_with_pauses(spec(2), 12);
+    let hissy = wav_from_fn(spec(1), 12, |t| {
+        hiss(t) + if t % 3.0 < 2.0 { voice(t) } else { 0.0 }
+    });
+
+    assert_split_matches_lazy(&bytes, |x| {
+        x.silence_threshold(-20.0)
+            .split_by_duration(Duration::from_secs(2))
+    });
+    assert_split_matches_lazy(&bytes, |x| {
+        x.silence_threshold(-20.0)
+            .split_by_duration(Duration::from_secs(3))
+            .cut_policy(CutPolicy::LongestSilence)
+            .overlap(Duration::from_millis(300))
+    });
+    assert_split_matches_lazy(&bytes, |x| {
+        x.split_by_duration(Duration::from_secs(2))
+            .cut_policy(CutPolicy::QuietestWindow)
+    });
+    assert_split_matches_lazy(&bytes, |x| x.silence_threshold(-20.0).split_into_parts(5));
+    assert_split_matches_lazy(&bytes, |x| {
+        x.silence_threshold(-20.0)
+            .split_on_silence(Duration::from_millis(300))
+    });
+    assert_split_matches_lazy(&bytes, |x| {
+        x.silence_threshold(-20.0)
+            .split_by_duration(Duration::from_millis(700))
+            .trim_silence(Duration::from_millis(50))
+            .silent_chunks(SilentChunks::Drop)
+    });
+    assert_split_matches_lazy(&hissy, |x| {
+        x.auto_silence_threshold()
+            .split_by_duration(Duration::from_secs(4))
+    });
+    assert_split_matches_lazy(&hissy, |x| {
+        x.voice_activity(VoiceActivityDetector::new())
+            .split_by_duration(Duration::from_secs(4))
+    });
+}
+
+#[test]
+fn split_chunks_share_one_buffer() {
+    let bytes = tone_with_pauses(spec(2), 10);
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+
+    let opts = SplitOpts::builder()
+        .codec(wav_splitter.codec())
+        .silence_threshold(-20.0)
+        .split_by_duration(Duration::from_secs(2))
+        .overlap(Duration::from_millis(200))
+        .build()
+        .unwrap();
+
+    let res = wav_splitter.split_audio(opts).unwrap();
+    assert!(res.len() > 2);
+
+    // Each chunk is a view into the same buffer, at the position its audio starts at.
+    let first = &res[0];
+    for chunk in res.iter() {
+        let offset = (chunk.start_frame() - first.start_frame()) * 2;
+        assert_eq!(
+            chunk.as_ptr() as usize,
+            first.as_ptr() as usize + offset * std::mem::size_of::<i16>()
+        );
+    }
+
+    let owned = res[1].to_owned();
+    assert_eq!(&owned[..], &res[1][..]);
+    assert_ne!(owned.as_ptr(), res[1].as_ptr());
+    assert_eq!(owned.to_planned(), res[1].to_planned());
+    assert_eq!(owned.index(), res[1].index());
+
+    // Chunks outlive the result they came from.
+    let chunks = res.into_chunks();
+    let last = chunks.last().unwrap().clone();
+    drop(chunks);
+    assert_eq!(last.end_frame(), 80_000);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn memory_mapped_files_split_like_bytes() {
+    let bytes = tone_with_pauses(spec(2), 10);
+    let path = std::env::temp_dir().join(format!("dub-oxide-mmap-{}.wav", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+
+    // SAFETY: Nothing else touches the file while it's mapped.
+    let mut mapped = unsafe { WavSplitter::from_mmap(&path) }.unwrap();
+    let res = mapped.split_audio(pause_opts(mapped.codec())).unwrap();
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let expected = wav_splitter
+        .split_audio(pause_opts(wav_splitter.codec()))
+        .unwrap();
+
+    assert_eq!(res.len(), expected.len());
+    for (chunk, expected) in res.iter().zip(expected.iter()) {
+        assert_eq!(&chunk[..], &expected[..]);
+        assert_eq!(chunk.to_planned(), expected.to_planned());
+    }
+
+    drop(mapped);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn memory_mapped_chunks_view_the_file() {
+    let spec = WavSpec {
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+        ..spec(2)
+    };
+    let bytes = tone_with_pauses(spec, 10);
+    let path = std::env::temp_dir().join(format!("dub-oxide-mmap-f32-{}.wav", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+
+    // SAFETY: Nothing else touches the file while it's mapped.
+    let mapped = unsafe { WavSplitter::from_mmap(&path) }.unwrap();
+    let mut mapped = mapped.with_sample_type::<f32>().unwrap();
+    let res = mapped.split_audio(pause_opts(mapped.codec())).unwrap();
+    // The file can be closed while its chunks still view it.
+    drop(mapped);
+    std::fs::remove_file(&path).unwrap();
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes)
+        .unwrap()
+        .with_sample_type::<f32>()
+        .unwrap();
+    let expected = wav_splitter
+        .split_audio(pause_opts(wav_splitter.codec()))
+        .unwrap();
+
+    assert!(res.len() > 2);
+    assert_eq!(res.len(), expected.len());
+    let first = &res[0];
+    for (chunk, expected) in res.iter().zip(expected.iter()) {
+        assert_eq!(&chunk[..], &expected[..]);
+        assert_eq!(chunk.to_planned(), expected.to_planned());
+        assert_eq!(chunk.peak(), expected.peak());
+
+        // Every chunk views the file's samples where they sit.
+        let offset = (chunk.start_frame() - first.start_frame()) * 2;
+        assert_eq!(
+            chunk.as_ptr() as usize,
+            first.as_ptr() as usize + offset * std::mem::size_of::<f32>()
+        );
+    }
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn memory_mapped_files_that_need_decoding_still_split() {
+    let spec = WavSpec {
+        bits_per_sample: 24,
+        ..spec(1)
+    };
+    let bytes = tone_with_pauses(spec, 10);
+    let path = std::env::temp_dir().join(format!("dub-oxide-mmap-i24-{}.wav", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+
+    // SAFETY: Nothing else touches the file while it's mapped.
+    let mapped = unsafe { WavSplitter::from_mmap(&path) }.unwrap();
+    let mut mapped = mapped.with_sample_type::<i32>().unwrap();
+    let res = mapped.split_audio(pause_opts(mapped.codec())).unwrap();
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes)
+        .unwrap()
+        .with_sample_type::<i32>()
+        .unwrap();
+    let expected = wav_splitter
+        .split_audio(pause_opts(wav_splitter.codec()))
+        .unwrap();
+
+    assert_eq!(res.len(), expected.len());
+    for (chunk, expected) in res.iter().zip(expected.iter()) {
+        assert_eq!(&chunk[..], &expected[..]);
+        assert_eq!(chunk.to_planned(), expected.to_planned());
+    }
+
+    drop(mapped);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_splitting_matches_the_lazy_iterator() {
+    use dub_oxide::formats::async_wav::AsyncWavSplitter;
+    use tokio::io::AsyncWriteExt;
+    use tokio_stream::StreamExt;
+
+    let bytes = tone_with_pauses(spec(2), 10);
+
+    // Feed the upload through a small pipe so it arrives a piece at a time.
+    let (mut upload, body) = tokio::io::duplex(1024);
+    let sent = bytes.clone();
+    tokio::spawn(async move { upload.write_all(&sent).await.unwrap() });
+
+    let splitter = AsyncWavSplitter::<i16>::open(body).await.unwrap();
+    let opts = pause_opts(splitter.codec());
+    let res: Vec<_> = splitter.chunks(opts).collect().await;
+
+    let mut wav_splitter = WavSplitter::from_bytes(&bytes).unwrap();
+    let opts = pause_opts(wav_splitter.codec());
+    let expected: Vec<_> = wav_splitter.chunks(opts).collect();
+
+    assert!(expected.len() > 1);
+    assert_eq!(res.len(), expected.len());
+    for (chunk, expected) in res.into_iter().zip(expected) {
+        let (chunk, expected) = (chunk.unwrap(), expected.unwrap());
+        assert_eq!(&chunk[..], &expected[..]);
+        assert_eq!(chunk.to_planned(), expected.to_planned());
+        assert_eq!(chunk.index(), expected.index());
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_splitting_rejects_auto_silence_threshold() {
+    use dub_oxide::{error::Error, formats::async_wav::AsyncWavSplitter};
+    use tokio_stream::StreamExt;
+
+    let bytes = tone_with_pauses(spec(1), 4);
+
+    let splitter = AsyncWavSplitter::<i16>::open(Cursor::new(bytes))
+        .await
+        .unwrap();
+    let opts = SplitOpts::builder()
+        .codec(splitter.codec())
+        .auto_silence_threshold()
+        .split_by_duration(Duration::from_secs(2))
+        .build()
+        .unwrap();
+    let res: Vec<_> = splitter.chunks(opts).collect().await;
+
+    assert_eq!(res.len(), 1);
+    assert!(matches!(res[0], Err(Error::Unsupported(_))));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_splitting_rejects_invalid_headers() {
+    use dub_oxide::{error::Error, formats::async_wav::AsyncWavSplitter};
+
+    let res = AsyncWavSplitter::<i16>::open(&b"not a wav file"[..]).await;
+
+    assert!(matches!(res, Err(Error::Hound(_))));
+}
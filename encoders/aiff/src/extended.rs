@@ -1,7 +1,6 @@
 //! Extended-precision 80-bit floating-point numbers (f80).
 //! With thanks to <https://github.com/depp/extended-rs> for the implementation, added here for maintainability purposes.
 
-#[warn(missing_docs)]
 use std::convert::From;
 
 /// An 80-bit extended floating-point number.
@@ -2,8 +2,9 @@ use std::io::Write;
 
 pub mod extended;
 
+#[allow(clippy::upper_case_acronyms)]
 type TODO = Box<dyn std::error::Error>;
-use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+use byteorder::{BigEndian, WriteBytesExt};
 use extended::Extended;
 
 #[derive(Debug)]
@@ -42,7 +43,7 @@ impl AiffEncoder {
         self.samples.iter().flat_map(|x| x.to_le_bytes()).collect()
     }
 
-    pub fn comm_chunk(&self) -> CommChunk {
+    pub fn comm_chunk(&self) -> CommChunk<'_> {
         let AiffHeader {
             sample_rate,
             num_channels,
@@ -52,7 +53,7 @@ impl AiffEncoder {
         CommChunk::new(num_channels, self.samples.len() as u32, 16, sample_rate)
     }
 
-    pub fn sound_chunk(&self) -> SoundChunk {
+    pub fn sound_chunk(&self) -> SoundChunk<'_> {
         let bytes = self.convert_to_u8_bytes();
         SoundChunk::new(bytes)
     }